@@ -1,5 +1,7 @@
+#[cfg(feature = "download")]
 #[path = "build/bundled.rs"]
 mod bundled;
+#[cfg(not(feature = "download"))]
 #[path = "build/cmake_probe.rs"]
 mod cmake_probe;
 
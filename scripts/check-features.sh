@@ -0,0 +1,28 @@
+#!/usr/bin/env bash
+# Run clippy with each feature on its own, so that code only used by some features is gated
+# correctly. Extra arguments are passed to cargo, e.g. `--offline`.
+set -euo pipefail
+cd "$(dirname "$0")/.."
+
+features=$(sed -n '/^\[features\]/,/^\[/p' Cargo.toml | grep -oE '^[a-z0-9_]+' | grep -v '^default$')
+# optional dependencies without an explicit feature
+features="$features tokio"
+
+combos=("" "toolkit,adb" "toolkit,win32")
+for feature in $features; do
+    combos+=("$feature")
+done
+
+failed=()
+for combo in "${combos[@]}"; do
+    echo "==> --no-default-features --features '$combo'"
+    if ! cargo clippy --all-targets --no-default-features --features "$combo" "$@" -- -D warnings; then
+        failed+=("$combo")
+    fi
+done
+
+if [ ${#failed[@]} -ne 0 ]; then
+    echo "failed feature sets:"
+    printf "  '%s'\n" "${failed[@]}"
+    exit 1
+fi
@@ -1,6 +1,9 @@
 use std::ffi::c_void;
 
-use crate::{internal, maa_bool, Error, MaaResult};
+use crate::{common::Rect, internal, maa_bool, Error, MaaResult};
+
+/// OpenCV type of 8 bit BGR images, the type of screenshots.
+const CV_8UC3: i32 = 16;
 
 pub struct MaaImageBuffer {
     pub(crate) handle: internal::MaaImageBufferHandle,
@@ -35,7 +38,7 @@ impl MaaImageBuffer {
         maa_bool!(empty)
     }
 
-    pub fn clear(&self) -> MaaResult<()> {
+    pub fn clear(&mut self) -> MaaResult<()> {
         let ret = unsafe { internal::MaaClearImage(self.handle) };
         maa_bool!(ret, BufferError)
     }
@@ -44,6 +47,158 @@ impl MaaImageBuffer {
         unsafe { internal::MaaGetImageRawData(self.handle) }
     }
 
+    /// Borrow the raw pixel data without copying it.
+    ///
+    /// The data is `height` rows of `width` pixels, each laid out as described by [typ](Self::typ),
+    /// an OpenCV matrix type such as `CV_8UC3` (BGR, one byte per channel). Rows are contiguous.
+    ///
+    /// The slice points into memory owned by MaaFramework. It borrows the buffer, so the image
+    /// cannot be cleared or replaced while the slice is alive. Buffers handed to custom components
+    /// are only valid during the callback, so do not keep the slice (or a raw pointer derived from
+    /// it) beyond that.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image is empty or its type is not a known OpenCV type.
+    pub fn as_slice(&self) -> MaaResult<&[u8]> {
+        let data = self.get_raw();
+        if self.empty() || data.is_null() {
+            return Err(Error::BufferError);
+        }
+
+        let len = self.raw_size().ok_or(Error::BufferError)?;
+
+        Ok(unsafe { std::slice::from_raw_parts(data as *const u8, len) })
+    }
+
+    /// Copy the region `roi` into a new buffer. A `[x, y, width, height]` array can be passed
+    /// as well.
+    ///
+    /// MaaFramework does not share pixel data between image buffers, so there is no borrowing
+    /// view; use [as_slice](Self::as_slice) to read the full frame without copying.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `roi` is empty or does not lie within the image.
+    pub fn crop(&self, roi: impl Into<Rect>) -> MaaResult<MaaImageBuffer> {
+        let roi = roi.into();
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = roi;
+        if x < 0
+            || y < 0
+            || width <= 0
+            || height <= 0
+            || x.checked_add(width)
+                .is_none_or(|right| right > self.width())
+            || y.checked_add(height)
+                .is_none_or(|bottom| bottom > self.height())
+        {
+            return Err(Error::MaaImageRoiOutOfBoundsError(roi));
+        }
+
+        let data = self.as_slice()?;
+        let pixel_size = data.len() / (self.width() as usize * self.height() as usize);
+        let stride = self.width() as usize * pixel_size;
+        let row_size = width as usize * pixel_size;
+
+        let mut cropped = Vec::with_capacity(row_size * height as usize);
+        for row in y as usize..(y + height) as usize {
+            let start = row * stride + x as usize * pixel_size;
+            cropped.extend_from_slice(&data[start..start + row_size]);
+        }
+
+        let mut buffer = MaaImageBuffer::new();
+        unsafe {
+            buffer.set_raw(cropped.as_mut_ptr().cast(), width, height, self.typ())?;
+        }
+
+        Ok(buffer)
+    }
+
+    /// The most common color of the image, or of `roi` in it, as `(r, g, b)`.
+    ///
+    /// Pixels are grouped by color with 16 levels per channel, and the average of the largest
+    /// group is returned, so that noise does not split a flat area into many colors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image is not 8 bit BGR or `roi` does not lie within it.
+    pub fn dominant_color(&self, roi: Option<&Rect>) -> MaaResult<(u8, u8, u8)> {
+        let cropped;
+        let image = match roi {
+            Some(roi) => {
+                cropped = self.crop(*roi)?;
+                &cropped
+            }
+            None => self,
+        };
+
+        let mut groups = vec![(0u32, [0u64; 3]); 16 * 16 * 16];
+        for pixel in image.bgr_pixels()? {
+            let [b, g, r] = [pixel[0], pixel[1], pixel[2]];
+            let index = (r as usize >> 4) << 8 | (g as usize >> 4) << 4 | b as usize >> 4;
+            let (count, sum) = &mut groups[index];
+            *count += 1;
+            sum[0] += r as u64;
+            sum[1] += g as u64;
+            sum[2] += b as u64;
+        }
+
+        let (count, sum) = groups
+            .into_iter()
+            .max_by_key(|(count, _)| *count)
+            .filter(|(count, _)| *count > 0)
+            .ok_or(Error::BufferError)?;
+        let average = |sum: u64| (sum / count as u64) as u8;
+
+        Ok((average(sum[0]), average(sum[1]), average(sum[2])))
+    }
+
+    /// Count the pixels of each value, per channel in `[r, g, b]` order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image is not 8 bit BGR.
+    pub fn color_histogram(&self) -> MaaResult<[[u32; 256]; 3]> {
+        let mut histogram = [[0; 256]; 3];
+        for pixel in self.bgr_pixels()? {
+            histogram[0][pixel[2] as usize] += 1;
+            histogram[1][pixel[1] as usize] += 1;
+            histogram[2][pixel[0] as usize] += 1;
+        }
+
+        Ok(histogram)
+    }
+
+    fn bgr_pixels(&self) -> MaaResult<std::slice::ChunksExact<'_, u8>> {
+        if self.typ() != CV_8UC3 {
+            return Err(Error::BufferError);
+        }
+
+        Ok(self.as_slice()?.chunks_exact(3))
+    }
+
+    /// Size in bytes of the raw pixel data, derived from the size and the OpenCV type of the image.
+    fn raw_size(&self) -> Option<usize> {
+        let typ = self.typ();
+        let depth_size = match typ & 7 {
+            0 | 1 => 1,
+            2 | 3 | 7 => 2,
+            4 | 5 => 4,
+            6 => 8,
+            _ => return None,
+        };
+        let channels = ((typ >> 3) & 511) as usize + 1;
+        let width = usize::try_from(self.width()).ok()?;
+        let height = usize::try_from(self.height()).ok()?;
+
+        Some(width * height * channels * depth_size)
+    }
+
     pub fn width(&self) -> i32 {
         unsafe { internal::MaaGetImageWidth(self.handle) }
     }
@@ -67,19 +222,25 @@ impl MaaImageBuffer {
     /// # Safety
     ///
     /// data must be a valid pointer to a valid encoded image
-    pub unsafe fn set_raw(&self, data: *mut c_void, width: i32, height: i32, typ: i32) {
-        unsafe {
-            internal::MaaSetImageRawData(self.handle, data, width, height, typ);
-        }
+    pub unsafe fn set_raw(
+        &mut self,
+        data: *mut c_void,
+        width: i32,
+        height: i32,
+        typ: i32,
+    ) -> MaaResult<()> {
+        let ret = unsafe { internal::MaaSetImageRawData(self.handle, data, width, height, typ) };
+
+        maa_bool!(ret, BufferError)
     }
 
     /// # Safety
     ///
     /// data must be a valid pointer to a valid encoded image
-    pub unsafe fn set_encoded(&self, data: *mut u8, size: u64) {
-        unsafe {
-            internal::MaaSetImageEncoded(self.handle, data, size);
-        }
+    pub unsafe fn set_encoded(&mut self, data: *mut u8, size: u64) -> MaaResult<()> {
+        let ret = unsafe { internal::MaaSetImageEncoded(self.handle, data, size) };
+
+        maa_bool!(ret, BufferError)
     }
 }
 
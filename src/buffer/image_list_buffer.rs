@@ -29,6 +29,17 @@ impl MaaImageListBuffer {
         }
     }
 
+    /// Create a list holding a copy of each of `images`.
+    pub fn from_images(images: &[MaaImageBuffer]) -> MaaResult<Self> {
+        let list = Self::new();
+
+        for image in images {
+            list.append(image)?;
+        }
+
+        Ok(list)
+    }
+
     pub fn empty(&self) -> bool {
         let empty = unsafe { internal::MaaIsImageListEmpty(self.handle) };
 
@@ -51,8 +62,9 @@ impl MaaImageListBuffer {
         MaaImageBuffer::from(handle)
     }
 
-    pub fn append(&self, handle: MaaImageBuffer) -> MaaResult<()> {
-        let ret = unsafe { internal::MaaImageListAppend(self.handle, handle.handle) };
+    /// Append a copy of `image` to the list.
+    pub fn append(&self, image: &MaaImageBuffer) -> MaaResult<()> {
+        let ret = unsafe { internal::MaaImageListAppend(self.handle, image.handle) };
 
         maa_bool!(ret, BufferError)
     }
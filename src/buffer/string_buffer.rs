@@ -31,12 +31,33 @@ impl MaaStringBuffer {
         }
     }
 
+    /// The content of the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the content is not valid UTF-8, see [try_string](Self::try_string).
     pub fn string(&self) -> String {
         let result = unsafe { internal::MaaGetString(self.handle) };
 
         string!(result)
     }
 
+    /// The content of the buffer, or [Error::Utf8Error] if it is not valid UTF-8.
+    pub fn try_string(&self) -> MaaResult<String> {
+        Ok(String::from_utf8(self.bytes())?)
+    }
+
+    /// The raw content of the buffer.
+    pub fn bytes(&self) -> Vec<u8> {
+        let data = unsafe { internal::MaaGetString(self.handle) };
+        let size = unsafe { internal::MaaGetStringSize(self.handle) };
+        if data.is_null() || size == 0 {
+            return Vec::new();
+        }
+
+        unsafe { std::slice::from_raw_parts(data as *const u8, size as usize) }.to_vec()
+    }
+
     pub fn set_string(&self, content: &str) -> MaaResult<()> {
         let content_str = internal::to_cstring(content);
         let ret = unsafe { internal::MaaSetString(self.handle, content_str) };
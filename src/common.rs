@@ -0,0 +1,182 @@
+//! Small value types shared across the crate.
+
+use std::ops::{Add, Sub};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{buffer::rect_buffer::MaaRectBuffer, diff_task::Target};
+
+pub use crate::{MaaId, MaaIdExt};
+
+/// The id MaaFramework returns when an operation could not be posted.
+pub const INVALID_MAA_ID: MaaId = 0;
+
+/// A point in screenshot coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Point { x, y }
+    }
+
+    /// The point moved by `dx` and `dy`. Saturates instead of overflowing.
+    pub fn offset(self, dx: i32, dy: i32) -> Self {
+        Point::new(self.x.saturating_add(dx), self.y.saturating_add(dy))
+    }
+
+    /// The center of a `[x, y, w, h]` box, such as a recognition hit box.
+    pub fn center_of(rect: [i32; 4]) -> Self {
+        Rect::from(rect).center()
+    }
+}
+
+impl From<(i32, i32)> for Point {
+    fn from((x, y): (i32, i32)) -> Self {
+        Point::new(x, y)
+    }
+}
+
+impl From<Point> for (i32, i32) {
+    fn from(point: Point) -> Self {
+        (point.x, point.y)
+    }
+}
+
+/// Saturates instead of overflowing.
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Self::Output {
+        self.offset(rhs.x, rhs.y)
+    }
+}
+
+/// Saturates instead of overflowing.
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Self::Output {
+        Point::new(self.x.saturating_sub(rhs.x), self.y.saturating_sub(rhs.y))
+    }
+}
+
+/// A 1x1 area at the point, negative coordinates are clamped to 0.
+impl From<Point> for Target {
+    fn from(point: Point) -> Self {
+        Target::Area([point.x.max(0) as u32, point.y.max(0) as u32, 1, 1])
+    }
+}
+
+/// A rectangle in screenshot coordinates. Converts to and from the `[x, y, w, h]` arrays used in
+/// the rest of the crate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The right edge, exclusive. Saturates instead of overflowing.
+    pub fn right(&self) -> i32 {
+        self.x.saturating_add(self.width)
+    }
+
+    /// The bottom edge, exclusive. Saturates instead of overflowing.
+    pub fn bottom(&self) -> i32 {
+        self.y.saturating_add(self.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(
+            self.x.saturating_add(self.width / 2),
+            self.y.saturating_add(self.height / 2),
+        )
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        (self.x..self.right()).contains(&point.x) && (self.y..self.bottom()).contains(&point.y)
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The overlapping area, `None` if the rects only touch or do not overlap at all.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+
+        let rect = Rect::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y));
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    pub fn offset(self, dx: i32, dy: i32) -> Self {
+        Rect::new(
+            self.x.saturating_add(dx),
+            self.y.saturating_add(dy),
+            self.width,
+            self.height,
+        )
+    }
+}
+
+impl From<[i32; 4]> for Rect {
+    fn from([x, y, width, height]: [i32; 4]) -> Self {
+        Rect::new(x, y, width, height)
+    }
+}
+
+impl From<Rect> for [i32; 4] {
+    fn from(rect: Rect) -> Self {
+        [rect.x, rect.y, rect.width, rect.height]
+    }
+}
+
+impl From<&MaaRectBuffer> for Rect {
+    fn from(buffer: &MaaRectBuffer) -> Self {
+        Rect::new(buffer.x(), buffer.y(), buffer.width(), buffer.height())
+    }
+}
+
+impl From<Rect> for MaaRectBuffer {
+    fn from(rect: Rect) -> Self {
+        MaaRectBuffer::new()
+            .set_x(rect.x)
+            .set_y(rect.y)
+            .set_width(rect.width)
+            .set_height(rect.height)
+    }
+}
+
+/// The area of the rect, negative coordinates and sizes are clamped to 0.
+impl From<Rect> for Target {
+    fn from(rect: Rect) -> Self {
+        Target::Area([
+            rect.x.max(0) as u32,
+            rect.y.max(0) as u32,
+            rect.width.max(0) as u32,
+            rect.height.max(0) as u32,
+        ])
+    }
+}
@@ -1,11 +1,24 @@
-use std::{fmt::Display, ops::Deref};
+use std::{
+    ffi::c_void,
+    fmt::Display,
+    ops::Deref,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    buffer::{image_buffer::MaaImageBuffer, string_buffer::MaaStringBuffer},
+    common::Rect,
     error::Error,
     internal,
-    maa_bool, CallbackHandler, MaaResult, MaaStatus,
+    maa_bool, maa_id,
+    msg::MaaMsg,
+    CallbackHandler, MaaResult, MaaStatus,
 };
 
 #[cfg(feature = "adb")]
@@ -24,6 +37,9 @@ use self::adb::MaaAdbControllerType;
 #[cfg(feature = "custom_controller")]
 use crate::custom::custom_controller::MaaCustomController;
 
+#[cfg(all(feature = "adb", feature = "toolkit"))]
+use crate::toolkit::AdbDeviceInfo;
+
 #[cfg(feature = "dbg")]
 use self::dbg::MaaDbgControllerType;
 
@@ -32,6 +48,256 @@ use self::win32::{MaaWin32ControllerType, MaaWin32Hwnd};
 
 pub use internal::MaaCtrlId;
 
+type InputLogger = Box<dyn Fn(&MaaInputEvent) + Send + Sync>;
+
+const ANDROID_KEYCODE_HOME: i32 = 3;
+const ANDROID_KEYCODE_BACK: i32 = 4;
+const ANDROID_KEYCODE_ENTER: i32 = 66;
+const ANDROID_KEYCODE_APP_SWITCH: i32 = 187;
+
+/// Contact and pressure of [MaaControllerInstance::post_click_opts].
+///
+/// Defaults to contact 0 and pressure 1, like the `Click` action of the pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClickOptions {
+    pub contact: i32,
+    pub pressure: i32,
+}
+
+impl Default for ClickOptions {
+    fn default() -> Self {
+        ClickOptions {
+            contact: 0,
+            pressure: 1,
+        }
+    }
+}
+
+/// Random perturbation of clicks and swipes, see [MaaControllerInstance::set_humanizer].
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Humanizer {
+    /// Maximum offset in pixels added to each coordinate, in both directions.
+    pub click_jitter_px: i32,
+    /// Inclusive range of the delay in milliseconds slept before posting.
+    pub delay_range_ms: (u64, u64),
+}
+
+impl Humanizer {
+    fn jitter(&self, x: i32, y: i32) -> (i32, i32) {
+        if self.click_jitter_px <= 0 {
+            return (x, y);
+        }
+
+        // within -jitter..=jitter, which always fits in an i32
+        let jitter = self.click_jitter_px as i64;
+        let span = 2 * jitter as u64 + 1;
+        let dx = (random_below(span) as i64 - jitter) as i32;
+        let dy = (random_below(span) as i64 - jitter) as i32;
+        (x.saturating_add(dx), y.saturating_add(dy))
+    }
+
+    fn delay(&self) {
+        let (min, max) = self.delay_range_ms;
+        if max == 0 || max < min {
+            return;
+        }
+
+        let millis = match (max - min).checked_add(1) {
+            Some(span) => min + random_below(span),
+            // the whole u64 range
+            None => random_below(u64::MAX),
+        };
+        std::thread::sleep(Duration::from_millis(millis));
+    }
+}
+
+/// One minus the mean absolute difference of two frames, 0.0 if their sizes differ.
+fn similarity(a: &[u8], b: &[u8]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let diff: u64 = a.iter().zip(b).map(|(a, b)| a.abs_diff(*b) as u64).sum();
+    1.0 - diff as f64 / (a.len() as f64 * 255.0)
+}
+
+/// A random number in `0..bound`, good enough for jitter but not for anything else.
+fn random_below(bound: u64) -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    if let Ok(now) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    hasher.finish() % bound
+}
+
+/// An operation posted to a controller, as reported to the logger set with
+/// [MaaControllerInstance::set_input_logger].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MaaInputEvent {
+    Connect,
+    Click {
+        x: i32,
+        y: i32,
+    },
+    Swipe {
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        duration: i32,
+    },
+    PressKey {
+        keycode: i32,
+    },
+    InputText {
+        text: String,
+    },
+    TouchDown {
+        contact: i32,
+        x: i32,
+        y: i32,
+        pressure: i32,
+    },
+    TouchMove {
+        contact: i32,
+        x: i32,
+        y: i32,
+        pressure: i32,
+    },
+    TouchUp {
+        contact: i32,
+    },
+    Screencap,
+}
+
+/// Screen orientation, see [MaaControllerInstance::orientation].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaaOrientation {
+    Portrait,
+    Landscape,
+}
+
+/// Connection state of a controller, as sent by [MaaConnectionEvents].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaaConnectionState {
+    Connected,
+    Disconnected,
+}
+
+/// A [CallbackHandler] that sends the connection state of a controller to a channel.
+///
+/// Pass it as the handler of the controller, then receive from the channel to react to
+/// transitions instead of polling [MaaControllerInstance::connected]. MaaFramework only reports
+/// the outcome of connection attempts, so a lost connection shows up as `Disconnected` when the
+/// next connection attempt fails.
+///
+/// ```ignore
+/// let (events, receiver) = MaaConnectionEvents::new();
+/// let controller = MaaControllerInstance::new_adb(adb_path, address, controller_type, "{}", agent_path, Some(events));
+/// for state in receiver {
+///     // pause or resume
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MaaConnectionEvents {
+    sender: Sender<MaaConnectionState>,
+}
+
+impl MaaConnectionEvents {
+    pub fn new() -> (Self, Receiver<MaaConnectionState>) {
+        let (sender, receiver) = channel();
+        (MaaConnectionEvents { sender }, receiver)
+    }
+}
+
+impl CallbackHandler for MaaConnectionEvents {
+    fn handle(&mut self, msg: MaaMsg) {
+        let state = match msg {
+            MaaMsg::ControllerConnectSuccess(_) => MaaConnectionState::Connected,
+            MaaMsg::ControllerConnectFailed(_) => MaaConnectionState::Disconnected,
+            _ => return,
+        };
+
+        // nobody listening any more
+        let _ = self.sender.send(state);
+    }
+}
+
+/// What a controller keeps from its messages.
+#[derive(Default)]
+pub(crate) struct ControllerState {
+    pub(crate) last_error: Option<String>,
+    resolution: Option<(i32, i32)>,
+}
+
+/// Callback argument of a controller: the user handler and the state shared with the controller.
+#[cfg(any(
+    feature = "adb",
+    feature = "win32",
+    feature = "dbg",
+    feature = "custom_controller"
+))]
+struct ControllerCallback<T> {
+    handler: Option<T>,
+    state: Arc<RwLock<ControllerState>>,
+}
+
+#[cfg(any(
+    feature = "adb",
+    feature = "win32",
+    feature = "dbg",
+    feature = "custom_controller"
+))]
+impl<T> ControllerCallback<T> {
+    fn into_raw(handler: Option<T>, state: &Arc<RwLock<ControllerState>>) -> *mut c_void {
+        let callback = ControllerCallback {
+            handler,
+            state: Arc::clone(state),
+        };
+        Box::into_raw(Box::new(callback)).cast()
+    }
+}
+
+#[cfg(any(
+    feature = "adb",
+    feature = "win32",
+    feature = "dbg",
+    feature = "custom_controller"
+))]
+unsafe extern "C" fn controller_callback_handler<T: CallbackHandler>(
+    msg: *const std::os::raw::c_char,
+    details_json: *const std::os::raw::c_char,
+    user_data: *mut c_void,
+) {
+    let msg = crate::string!(msg);
+    let details_json = crate::string!(details_json);
+    let maa_msg = MaaMsg::from(&msg, &details_json).unwrap();
+    let callback = &mut *(user_data as *mut ControllerCallback<T>);
+
+    match &maa_msg {
+        MaaMsg::ControllerConnectFailed(failed) => {
+            callback.state.write().unwrap().last_error = Some(failed.why.clone());
+        }
+        MaaMsg::ControllerConnectSuccess(_) => {
+            callback.state.write().unwrap().last_error = None;
+        }
+        MaaMsg::ControllerResolutionGot(resolution) => {
+            callback.state.write().unwrap().resolution =
+                Some((resolution.width, resolution.height));
+        }
+        _ => {}
+    }
+
+    if let Some(handler) = callback.handler.as_mut() {
+        handler.handle(maa_msg);
+    }
+}
+
+/// A leaked box and the function dropping it.
+type OwnedBox = (*mut c_void, unsafe fn(*mut c_void));
+
 /// A handle to a controller instance
 ///
 /// # Note
@@ -39,6 +305,14 @@ pub use internal::MaaCtrlId;
 /// See [MaaInstance](crate::instance::MaaInstance) for lifetime hints.
 pub struct MaaControllerInstance<T> {
     pub(crate) handle: internal::MaaControllerHandle,
+    input_logger: RwLock<Option<InputLogger>>,
+    humanizer: RwLock<Option<Humanizer>>,
+    state: Arc<RwLock<ControllerState>>,
+    destroy_at_drop: bool,
+    /// The callback argument passed to the framework, freed after the controller is destroyed.
+    callback: Option<OwnedBox>,
+    /// The custom controller of [new_custom](Self::new_custom) and how to drop it.
+    custom_controller: Option<OwnedBox>,
     _phantom: std::marker::PhantomData<T>,
 }
 
@@ -76,40 +350,53 @@ impl<T> MaaControllerInstance<T> {
         let config = internal::to_cstring(config);
         let agent_path = internal::to_cstring(agent_path);
 
+        let state = Arc::default();
+        let callback = ControllerCallback::into_raw(handler, &state);
+
         let handle = unsafe {
-            match handler {
-                Some(handler) => {
-                    let handler = Box::new(handler);
-                    let handler = Box::into_raw(handler);
-
-                    internal::MaaAdbControllerCreateV2(
-                        adb_path,
-                        address,
-                        controller_type.into(),
-                        config,
-                        agent_path,
-                        Some(internal::callback_handler::<T>),
-                        handler.cast(),
-                    )
-                }
-                None => internal::MaaAdbControllerCreateV2(
-                    adb_path,
-                    address,
-                    controller_type.into(),
-                    config,
-                    agent_path,
-                    None,
-                    std::ptr::null_mut(),
-                ),
-            }
+            internal::MaaAdbControllerCreateV2(
+                adb_path,
+                address,
+                controller_type.into(),
+                config,
+                agent_path,
+                Some(controller_callback_handler::<T>),
+                callback,
+            )
         };
 
         MaaControllerInstance {
             handle,
+            input_logger: RwLock::new(None),
+            humanizer: RwLock::new(None),
+            state,
+            destroy_at_drop: true,
+            callback: Some((callback, drop_box::<ControllerCallback<T>>)),
+            custom_controller: None,
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Create a new AdbController for a device found by [MaaToolkit](crate::toolkit::MaaToolkit).
+    ///
+    /// [AdbDeviceInfo] can be (de)serialized, so a device can be saved and reused without
+    /// scanning again.
+    #[cfg(all(feature = "adb", feature = "toolkit"))]
+    #[doc(cfg(all(feature = "adb", feature = "toolkit")))]
+    pub fn from_adb_device(device: &AdbDeviceInfo, agent_path: &str, handler: Option<T>) -> Self
+    where
+        T: CallbackHandler,
+    {
+        Self::new_adb(
+            &device.adb_path,
+            &device.adb_serial,
+            device.adb_controller_type,
+            &device.adb_config,
+            agent_path,
+            handler,
+        )
+    }
+
     #[cfg(feature = "win32")]
     #[doc(cfg(feature = "win32"))]
     pub fn new_win32(
@@ -120,30 +407,26 @@ impl<T> MaaControllerInstance<T> {
     where
         T: CallbackHandler,
     {
+        let state = Arc::default();
+        let callback = ControllerCallback::into_raw(handler, &state);
+
         let handle = unsafe {
-            match handler {
-                Some(handler) => {
-                    let handler = Box::new(handler);
-                    let handler = Box::into_raw(handler);
-
-                    internal::MaaWin32ControllerCreate(
-                        *hwnd,
-                        controller_type.into(),
-                        Some(internal::callback_handler::<T>),
-                        handler.cast(),
-                    )
-                }
-                None => internal::MaaWin32ControllerCreate(
-                    *hwnd,
-                    controller_type.into(),
-                    None,
-                    std::ptr::null_mut(),
-                ),
-            }
+            internal::MaaWin32ControllerCreate(
+                *hwnd,
+                controller_type.into(),
+                Some(controller_callback_handler::<T>),
+                callback,
+            )
         };
 
         MaaControllerInstance {
             handle,
+            input_logger: RwLock::new(None),
+            humanizer: RwLock::new(None),
+            state,
+            destroy_at_drop: true,
+            callback: Some((callback, drop_box::<ControllerCallback<T>>)),
+            custom_controller: None,
             _phantom: std::marker::PhantomData,
         }
     }
@@ -164,38 +447,63 @@ impl<T> MaaControllerInstance<T> {
         let write_path = internal::to_cstring(write_path);
         let config = internal::to_cstring(config);
 
+        let state = Arc::default();
+        let callback = ControllerCallback::into_raw(handler, &state);
+
         let handle = unsafe {
-            match handler {
-                Some(handler) => {
-                    let handler = Box::new(handler);
-                    let handler = Box::into_raw(handler);
-
-                    internal::MaaDbgControllerCreate(
-                        read_path,
-                        write_path,
-                        controller_type.into(),
-                        config,
-                        Some(internal::callback_handler::<T>),
-                        handler.cast(),
-                    )
-                }
-                None => internal::MaaDbgControllerCreate(
-                    read_path,
-                    write_path,
-                    controller_type.into(),
-                    config,
-                    None,
-                    std::ptr::null_mut(),
-                ),
-            }
+            internal::MaaDbgControllerCreate(
+                read_path,
+                write_path,
+                controller_type.into(),
+                config,
+                Some(controller_callback_handler::<T>),
+                callback,
+            )
         };
 
         MaaControllerInstance {
             handle,
+            input_logger: RwLock::new(None),
+            humanizer: RwLock::new(None),
+            state,
+            destroy_at_drop: true,
+            callback: Some((callback, drop_box::<ControllerCallback<T>>)),
+            custom_controller: None,
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Create a debug controller that serves the images in `image_path` as screenshots.
+    ///
+    /// The images are returned in turn on every screencap and inputs are accepted and ignored.
+    /// This needs no device or window, so it can stand in for an adb or win32 controller in
+    /// headless tests.
+    #[cfg(feature = "dbg")]
+    #[doc(cfg(feature = "dbg"))]
+    pub fn new_carousel_image(image_path: &str, handler: Option<T>) -> Self
+    where
+        T: CallbackHandler,
+    {
+        Self::new_dbg(
+            image_path,
+            "",
+            MaaDbgControllerType::CarouselImage,
+            "{}",
+            handler,
+        )
+    }
+
+    /// Create a controller backed by a [MaaCustomController], which is dropped with it.
+    ///
+    /// To read the state of the custom controller afterwards, pass a clone of an
+    /// `Arc<Mutex<C>>` and keep the other one:
+    ///
+    /// ```ignore
+    /// let frames = Arc::new(Mutex::new(FrameCounter::default()));
+    /// let controller = MaaControllerInstance::new_custom(Arc::clone(&frames), None);
+    /// // ...
+    /// println!("{} frames", frames.lock().unwrap().count);
+    /// ```
     #[cfg(feature = "custom_controller")]
     #[doc(cfg(feature = "custom_controller"))]
     pub fn new_custom<C>(controller: C, handler: Option<T>) -> Self
@@ -224,37 +532,50 @@ impl<T> MaaControllerInstance<T> {
         let controller_api = Box::new(controller_api);
         let controller_api = Box::into_raw(controller_api);
 
+        let state = Arc::default();
+        let controller = custom_controller::CustomControllerArg {
+            controller,
+            state: Arc::clone(&state),
+        };
+        let controller = Box::into_raw(Box::new(controller)) as *mut c_void;
+
+        let callback = ControllerCallback::into_raw(handler, &state);
+
         let handle = unsafe {
-            match handler {
-                Some(handler) => {
-                    let handler = Box::new(handler);
-                    let handler = Box::into_raw(handler);
-
-                    internal::MaaCustomControllerCreate(
-                        controller_api,
-                        &controller as *const C as *mut C as *mut std::ffi::c_void,
-                        Some(internal::callback_handler::<T>),
-                        handler.cast(),
-                    )
-                }
-                None => internal::MaaCustomControllerCreate(
-                    controller_api,
-                    &controller as *const C as *mut C as *mut std::ffi::c_void,
-                    None,
-                    std::ptr::null_mut(),
-                ),
-            }
+            internal::MaaCustomControllerCreate(
+                controller_api,
+                controller,
+                Some(controller_callback_handler::<T>),
+                callback,
+            )
         };
 
         MaaControllerInstance {
             handle,
+            input_logger: RwLock::new(None),
+            humanizer: RwLock::new(None),
+            state,
+            destroy_at_drop: true,
+            callback: Some((callback, drop_box::<ControllerCallback<T>>)),
+            custom_controller: Some((
+                controller,
+                drop_box::<custom_controller::CustomControllerArg<C>>,
+            )),
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Wrap a controller owned by someone else, e.g. the one bound to an instance. It is not
+    /// destroyed on drop.
     pub(crate) fn new_from_handle(handle: internal::MaaControllerHandle) -> Self {
         MaaControllerInstance {
             handle,
+            input_logger: RwLock::new(None),
+            humanizer: RwLock::new(None),
+            state: Arc::default(),
+            destroy_at_drop: false,
+            callback: None,
+            custom_controller: None,
             _phantom: std::marker::PhantomData,
         }
     }
@@ -302,41 +623,535 @@ impl<T> MaaControllerInstance<T> {
         }
     }
 
-    pub fn post_connect(&self) -> MaaCtrlId {
-        unsafe { internal::MaaControllerPostConnection(self.handle) }
+    /// Scale screenshots so that their long side is `long_side` pixels, keeping the aspect ratio.
+    ///
+    /// Only one of the long and short side can be set, the last one set wins.
+    pub fn set_screenshot_target_long_side(&self, long_side: i32) -> MaaResult<()> {
+        self.set_controller_option(MaaControllerOption::ScreenshotTargetLongSide(long_side))
+    }
+
+    /// Scale screenshots so that their short side is `short_side` pixels, keeping the aspect
+    /// ratio. This is the default, with 720.
+    ///
+    /// Only one of the long and short side can be set, the last one set wins.
+    pub fn set_screenshot_target_short_side(&self, short_side: i32) -> MaaResult<()> {
+        self.set_controller_option(MaaControllerOption::ScreenshotTargetShortSide(short_side))
+    }
+
+    /// Set the activity started by the `StartApp` action, e.g.
+    /// `com.hypergryph.arknights/com.u8.sdk.U8UnityContext`.
+    pub fn set_default_app_package_entry(&self, entry: &str) -> MaaResult<()> {
+        self.set_controller_option(MaaControllerOption::DefaultAppPackageEntry(
+            entry.to_owned(),
+        ))
+    }
+
+    /// Set the package stopped by the `StopApp` action, e.g. `com.hypergryph.arknights`.
+    pub fn set_default_app_package(&self, package: &str) -> MaaResult<()> {
+        self.set_controller_option(MaaControllerOption::DefaultAppPackage(package.to_owned()))
+    }
+
+    /// Dump all screenshots and actions of this controller.
+    ///
+    /// Recording is also on if the global recording option is set.
+    pub fn set_recording(&self, recording: bool) -> MaaResult<()> {
+        self.set_controller_option(MaaControllerOption::Recording(recording))
+    }
+
+    /// Set a closure that is called with every operation posted through this controller.
+    ///
+    /// This is a client side log of what was requested, including the parameters, and is called
+    /// before the operation is handed to MaaFramework. Framework notifications still go through the
+    /// [CallbackHandler]. Replaces any previously set logger.
+    pub fn set_input_logger<F>(&self, logger: F)
+    where
+        F: Fn(&MaaInputEvent) + Send + Sync + 'static,
+    {
+        *self.input_logger.write().unwrap() = Some(Box::new(logger));
+    }
+
+    pub fn clear_input_logger(&self) {
+        *self.input_logger.write().unwrap() = None;
     }
 
-    pub fn post_click(&self, x: i32, y: i32) -> MaaCtrlId {
-        unsafe { internal::MaaControllerPostClick(self.handle, x, y) }
+    /// Jitter the coordinates of [post_click](Self::post_click),
+    /// [post_click_opts](Self::post_click_opts) and [post_swipe](Self::post_swipe) and sleep a
+    /// random delay before posting them.
+    ///
+    /// This is best effort: it only applies to operations posted through this controller, not to
+    /// the ones of the pipeline, and the delay blocks the caller. Off by default. Replaces any
+    /// previously set humanizer.
+    pub fn set_humanizer(&self, humanizer: Humanizer) {
+        *self.humanizer.write().unwrap() = Some(humanizer);
     }
 
-    pub fn post_swipe(&self, x1: i32, y1: i32, x2: i32, y2: i32, duration: i32) -> MaaCtrlId {
-        unsafe { internal::MaaControllerPostSwipe(self.handle, x1, y1, x2, y2, duration) }
+    pub fn clear_humanizer(&self) {
+        *self.humanizer.write().unwrap() = None;
     }
 
-    pub fn post_press_key(&self, keycode: i32) -> MaaCtrlId {
-        unsafe { internal::MaaControllerPostPressKey(self.handle, keycode) }
+    fn log_input(&self, event: MaaInputEvent) {
+        if let Some(logger) = self.input_logger.read().unwrap().as_ref() {
+            logger(&event);
+        }
     }
 
-    pub fn post_input_text(&self, text: &str) -> MaaCtrlId {
+    pub fn post_connect(&self) -> MaaResult<MaaCtrlId> {
+        self.log_input(MaaInputEvent::Connect);
+        let id = unsafe { internal::MaaControllerPostConnection(self.handle) };
+
+        maa_id!(id, MaaControllerPostError, "connection".to_owned())
+    }
+
+    /// Wait the humanizer delay and jitter `points`, if a humanizer is set.
+    fn humanize<const N: usize>(&self, points: [(i32, i32); N]) -> [(i32, i32); N] {
+        let Some(humanizer) = *self.humanizer.read().unwrap() else {
+            return points;
+        };
+
+        humanizer.delay();
+        points.map(|(x, y)| humanizer.jitter(x, y))
+    }
+
+    pub fn post_click(&self, x: i32, y: i32) -> MaaResult<MaaCtrlId> {
+        let [(x, y)] = self.humanize([(x, y)]);
+        self.log_input(MaaInputEvent::Click { x, y });
+        let id = unsafe { internal::MaaControllerPostClick(self.handle, x, y) };
+
+        maa_id!(id, MaaControllerPostError, "click".to_owned())
+    }
+
+    /// Click with a given contact and pressure, see [ClickOptions].
+    ///
+    /// MaaFramework 1.8 has no click taking them, so this posts a touch down and a touch up. The
+    /// returned id is the one of the touch up.
+    pub fn post_click_opts(&self, x: i32, y: i32, options: ClickOptions) -> MaaResult<MaaCtrlId> {
+        let [(x, y)] = self.humanize([(x, y)]);
+        self.post_touch_down(options.contact, x, y, options.pressure)?;
+        self.post_touch_up(options.contact)
+    }
+
+    pub fn post_swipe(
+        &self,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        duration: i32,
+    ) -> MaaResult<MaaCtrlId> {
+        let [(x1, y1), (x2, y2)] = self.humanize([(x1, y1), (x2, y2)]);
+        self.log_input(MaaInputEvent::Swipe {
+            x1,
+            y1,
+            x2,
+            y2,
+            duration,
+        });
+        let id = unsafe { internal::MaaControllerPostSwipe(self.handle, x1, y1, x2, y2, duration) };
+
+        maa_id!(id, MaaControllerPostError, "swipe".to_owned())
+    }
+
+    pub fn post_press_key(&self, keycode: i32) -> MaaResult<MaaCtrlId> {
+        self.log_input(MaaInputEvent::PressKey { keycode });
+        let id = unsafe { internal::MaaControllerPostPressKey(self.handle, keycode) };
+
+        maa_id!(id, MaaControllerPostError, "press key".to_owned())
+    }
+
+    /// Press the Android back key.
+    ///
+    /// This and the other navigation key helpers post Android keycodes. The controller does not
+    /// know which kind of device it drives, so on a Win32 controller the code is sent as is and
+    /// means a different key; use [post_press_key](Self::post_press_key) with a virtual key code
+    /// there.
+    pub fn post_back(&self) -> MaaResult<MaaCtrlId> {
+        self.post_press_key(ANDROID_KEYCODE_BACK)
+    }
+
+    /// Press the Android home key, see [post_back](Self::post_back).
+    pub fn post_home(&self) -> MaaResult<MaaCtrlId> {
+        self.post_press_key(ANDROID_KEYCODE_HOME)
+    }
+
+    /// Press the Android recent apps key, see [post_back](Self::post_back).
+    pub fn post_recent_apps(&self) -> MaaResult<MaaCtrlId> {
+        self.post_press_key(ANDROID_KEYCODE_APP_SWITCH)
+    }
+
+    /// Press the Android enter key, see [post_back](Self::post_back).
+    pub fn post_enter(&self) -> MaaResult<MaaCtrlId> {
+        self.post_press_key(ANDROID_KEYCODE_ENTER)
+    }
+
+    pub fn post_input_text(&self, text: &str) -> MaaResult<MaaCtrlId> {
+        self.log_input(MaaInputEvent::InputText {
+            text: text.to_owned(),
+        });
         let text = internal::to_cstring(text);
-        unsafe { internal::MaaControllerPostInputText(self.handle, text) }
+        let id = unsafe { internal::MaaControllerPostInputText(self.handle, text) };
+
+        maa_id!(id, MaaControllerPostError, "input text".to_owned())
+    }
+
+    /// Click at `(fx, fy)` given as fractions of the screenshot size, so that `(0.5, 0.5)` is the
+    /// center of the screen on any device. Values are clamped to `[0, 1]`.
+    ///
+    /// The size is taken from the last screenshot, so a screencap must have been done before.
+    pub fn post_click_norm(&self, fx: f32, fy: f32) -> MaaResult<MaaCtrlId> {
+        let (x, y) = self.denormalize(fx, fy)?;
+        self.post_click(x, y)
+    }
+
+    /// Swipe between points given as fractions of the screenshot size, see
+    /// [post_click_norm](Self::post_click_norm).
+    pub fn post_swipe_norm(
+        &self,
+        from: (f32, f32),
+        to: (f32, f32),
+        duration: i32,
+    ) -> MaaResult<MaaCtrlId> {
+        let (x1, y1) = self.denormalize(from.0, from.1)?;
+        let (x2, y2) = self.denormalize(to.0, to.1)?;
+        self.post_swipe(x1, y1, x2, y2, duration)
+    }
+
+    fn denormalize(&self, fx: f32, fy: f32) -> MaaResult<(i32, i32)> {
+        let image = self.cached_image()?;
+        if image.empty() {
+            return Err(Error::MaaControllerGetImageError);
+        }
+
+        let x = fx.clamp(0.0, 1.0) * (image.width() - 1) as f32;
+        let y = fy.clamp(0.0, 1.0) * (image.height() - 1) as f32;
+        Ok((x.round() as i32, y.round() as i32))
+    }
+
+    /// Orientation of the screen, judged from the shape of the last screenshot.
+    ///
+    /// MaaFramework does not report the device rotation, so reversed orientations cannot be told
+    /// apart from the normal ones. A screencap must have been done before.
+    pub fn orientation(&self) -> MaaResult<MaaOrientation> {
+        let image = self.cached_image()?;
+        if image.empty() {
+            return Err(Error::MaaControllerGetImageError);
+        }
+
+        if image.width() >= image.height() {
+            Ok(MaaOrientation::Landscape)
+        } else {
+            Ok(MaaOrientation::Portrait)
+        }
+    }
+
+    /// Ratio of the screenshot size to the raw device resolution.
+    ///
+    /// Screenshots are scaled as set by
+    /// [set_screenshot_target_long_side](Self::set_screenshot_target_long_side) or
+    /// [set_screenshot_target_short_side](Self::set_screenshot_target_short_side), and recognition
+    /// boxes and clicks are in screenshot coordinates. Needs a connection and a screencap.
+    pub fn screenshot_scale(&self) -> MaaResult<f64> {
+        let (raw_width, _) = self
+            .state
+            .read()
+            .unwrap()
+            .resolution
+            .ok_or(Error::MaaControllerConnectError)?;
+        let image = self.cached_image()?;
+        if image.empty() || raw_width <= 0 {
+            return Err(Error::MaaControllerGetImageError);
+        }
+
+        Ok(image.width() as f64 / raw_width as f64)
+    }
+
+    /// Convert a rect from screenshot to raw device coordinates.
+    pub fn to_raw_coords(&self, scaled: Rect) -> MaaResult<Rect> {
+        let scale = self.screenshot_scale()?;
+        let raw = <[i32; 4]>::from(scaled).map(|v| (v as f64 / scale).round() as i32);
+        Ok(Rect::from(raw))
+    }
+
+    /// Convert a rect from raw device to screenshot coordinates.
+    pub fn to_scaled_coords(&self, raw: Rect) -> MaaResult<Rect> {
+        let scale = self.screenshot_scale()?;
+        let scaled = <[i32; 4]>::from(raw).map(|v| (v as f64 * scale).round() as i32);
+        Ok(Rect::from(scaled))
+    }
+
+    /// The last screenshot taken by this controller.
+    pub fn cached_image(&self) -> MaaResult<MaaImageBuffer> {
+        let buffer = MaaImageBuffer::new();
+
+        let ret = unsafe { internal::MaaControllerGetImage(self.handle, buffer.handle) };
+
+        if maa_bool!(ret) {
+            Ok(buffer)
+        } else {
+            Err(Error::MaaControllerGetImageError)
+        }
+    }
+
+    /// Start a touch with `contact` that is released when dropped, see [MaaTouchSequence].
+    pub fn touch_sequence(&self, contact: i32) -> MaaTouchSequence<'_, T> {
+        MaaTouchSequence {
+            controller: self,
+            contact,
+            down: false,
+        }
+    }
+
+    /// Input `text` in pieces of at most `chunk_size` characters, waiting for each piece and
+    /// sleeping `delay` between them.
+    ///
+    /// Some input methods drop the end of long strings. This blocks until the whole text is
+    /// entered and fails on the first piece that does not go through.
+    pub fn post_input_text_chunked(
+        &self,
+        text: &str,
+        chunk_size: usize,
+        delay: Duration,
+    ) -> MaaResult<()> {
+        let chars: Vec<char> = text.chars().collect();
+
+        for (i, chunk) in chars.chunks(chunk_size.max(1)).enumerate() {
+            if i > 0 {
+                std::thread::sleep(delay);
+            }
+
+            let chunk: String = chunk.iter().collect();
+            let id = self.post_input_text(&chunk)?;
+            if !matches!(self.wait(id)?, MaaStatus::Success) {
+                return Err(Error::MaaControllerActionError("input text".to_owned()));
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn post_touch_down(&self, contact: i32, x: i32, y: i32, pressure: i32) -> MaaCtrlId {
-        unsafe { internal::MaaControllerPostTouchDown(self.handle, contact, x, y, pressure) }
+    pub fn post_touch_down(
+        &self,
+        contact: i32,
+        x: i32,
+        y: i32,
+        pressure: i32,
+    ) -> MaaResult<MaaCtrlId> {
+        self.log_input(MaaInputEvent::TouchDown {
+            contact,
+            x,
+            y,
+            pressure,
+        });
+        let id =
+            unsafe { internal::MaaControllerPostTouchDown(self.handle, contact, x, y, pressure) };
+
+        maa_id!(id, MaaControllerPostError, "touch down".to_owned())
+    }
+
+    pub fn post_touch_move(
+        &self,
+        contact: i32,
+        x: i32,
+        y: i32,
+        pressure: i32,
+    ) -> MaaResult<MaaCtrlId> {
+        self.log_input(MaaInputEvent::TouchMove {
+            contact,
+            x,
+            y,
+            pressure,
+        });
+        let id =
+            unsafe { internal::MaaControllerPostTouchMove(self.handle, contact, x, y, pressure) };
+
+        maa_id!(id, MaaControllerPostError, "touch move".to_owned())
     }
 
-    pub fn post_touch_move(&self, contact: i32, x: i32, y: i32, pressure: i32) -> MaaCtrlId {
-        unsafe { internal::MaaControllerPostTouchMove(self.handle, contact, x, y, pressure) }
+    pub fn post_touch_up(&self, contact: i32) -> MaaResult<MaaCtrlId> {
+        self.log_input(MaaInputEvent::TouchUp { contact });
+        let id = unsafe { internal::MaaControllerPostTouchUp(self.handle, contact) };
+
+        maa_id!(id, MaaControllerPostError, "touch up".to_owned())
     }
 
-    pub fn post_touch_up(&self, contact: i32) -> MaaCtrlId {
-        unsafe { internal::MaaControllerPostTouchUp(self.handle, contact) }
+    pub fn post_screencap(&self) -> MaaResult<MaaCtrlId> {
+        self.log_input(MaaInputEvent::Screencap);
+        let id = unsafe { internal::MaaControllerPostScreencap(self.handle) };
+
+        maa_id!(id, MaaControllerPostError, "screencap".to_owned())
     }
 
-    pub fn post_screencap(&self) -> MaaCtrlId {
-        unsafe { internal::MaaControllerPostScreencap(self.handle) }
+    /// Post a two finger pinch centred on `center`.
+    ///
+    /// Contacts `0` and `1` are put down `start_dist` pixels apart on a horizontal line through
+    /// `center` and moved symmetrically until they are `end_dist` apart. An `end_dist` larger than
+    /// `start_dist` zooms in, a smaller one zooms out.
+    ///
+    /// The moves have to be spaced out from the caller side, so unlike the other `post_*` methods
+    /// this blocks for roughly `duration` milliseconds. Both contacts are released even if a move
+    /// fails. The returned id is the one of the final touch up, so waiting on it waits for the
+    /// whole gesture.
+    pub fn post_pinch(
+        &self,
+        center: (i32, i32),
+        start_dist: i32,
+        end_dist: i32,
+        duration: i32,
+    ) -> MaaResult<MaaCtrlId> {
+        const STEP_MS: i32 = 10;
+        const PRESSURE: i32 = 1;
+
+        let (x, y) = center;
+        let steps = (duration / STEP_MS).max(1);
+        let half = |step: i32| {
+            let (start, end) = (i64::from(start_dist), i64::from(end_dist));
+            let dist = start + (end - start) * i64::from(step) / i64::from(steps);
+            (dist / 2).clamp(i32::MIN.into(), i32::MAX.into()) as i32
+        };
+
+        self.post_touch_down(0, x - half(0), y, PRESSURE)?;
+
+        let moved = self
+            .post_touch_down(1, x + half(0), y, PRESSURE)
+            .and_then(|_| {
+                for step in 1..=steps {
+                    self.post_touch_move(0, x - half(step), y, PRESSURE)?;
+                    let id = self.post_touch_move(1, x + half(step), y, PRESSURE)?;
+                    self.wait(id)?;
+                    std::thread::sleep(Duration::from_millis(STEP_MS as u64));
+                }
+                Ok(())
+            });
+
+        let up = self.post_touch_up(0);
+        let last = self.post_touch_up(1);
+
+        moved?;
+        up?;
+        last
+    }
+
+    /// Drag `contact` along `path`, as one gesture lasting about `total_duration` milliseconds.
+    ///
+    /// The contact is put down on the first point, moved to each of the others at even
+    /// intervals and released on the last one. Like [post_pinch](Self::post_pinch), this blocks
+    /// for the whole gesture, releases the contact even if a move fails, and returns the id of
+    /// the touch up.
+    pub fn post_touch_path(
+        &self,
+        contact: i32,
+        path: &[(i32, i32)],
+        total_duration: i32,
+        pressure: i32,
+    ) -> MaaResult<MaaCtrlId> {
+        let Some((&(x, y), rest)) = path.split_first() else {
+            return Err(Error::MaaControllerActionError(
+                "follow an empty touch path".to_owned(),
+            ));
+        };
+        let step = Duration::from_millis(total_duration.max(0) as u64 / rest.len().max(1) as u64);
+
+        self.post_touch_down(contact, x, y, pressure)?;
+
+        let moved = rest.iter().try_for_each(|&(x, y)| -> MaaResult<()> {
+            let id = self.post_touch_move(contact, x, y, pressure)?;
+            self.wait(id)?;
+            std::thread::sleep(step);
+            Ok(())
+        });
+
+        let up = self.post_touch_up(contact);
+
+        moved?;
+        up
+    }
+
+    /// Click and wait for it, clicking again up to `retries` times if it fails.
+    pub fn click_reliable(&self, x: i32, y: i32, retries: u32) -> MaaResult<()> {
+        self.post_reliable("click", retries, || self.post_click(x, y))
+    }
+
+    /// Swipe and wait for it, swiping again up to `retries` times if it fails.
+    pub fn swipe_reliable(
+        &self,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        duration: i32,
+        retries: u32,
+    ) -> MaaResult<()> {
+        self.post_reliable("swipe", retries, || {
+            self.post_swipe(x1, y1, x2, y2, duration)
+        })
+    }
+
+    /// Input `text` and wait for it, inputting again up to `retries` times if it fails.
+    pub fn input_text_reliable(&self, text: &str, retries: u32) -> MaaResult<()> {
+        self.post_reliable("input text", retries, || self.post_input_text(text))
+    }
+
+    fn post_reliable<F>(&self, action: &str, retries: u32, post: F) -> MaaResult<()>
+    where
+        F: Fn() -> MaaResult<MaaCtrlId>,
+    {
+        for _ in 0..=retries {
+            let id = post()?;
+            if matches!(self.wait(id)?, MaaStatus::Success) {
+                return Ok(());
+            }
+        }
+
+        Err(Error::MaaControllerActionError(action.to_owned()))
+    }
+
+    /// Take screenshots until the screen, or `roi` of it, stays the same for `stable_for`.
+    ///
+    /// Two frames are the same if their similarity, one minus the mean absolute difference of the
+    /// pixels scaled to 0.0..=1.0, is at least `threshold`, e.g. 0.95 like the `wait_freezes`
+    /// default of the pipeline.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::MaaControllerNotStableError] if the screen is still changing after
+    /// `timeout`.
+    pub fn wait_until_stable(
+        &self,
+        roi: Option<Rect>,
+        threshold: f64,
+        stable_for: Duration,
+        timeout: Duration,
+    ) -> MaaResult<()> {
+        let start = Instant::now();
+        let mut previous: Option<Vec<u8>> = None;
+        let mut stable_since = start;
+
+        loop {
+            let id = self.post_screencap()?;
+            if !matches!(self.wait(id)?, MaaStatus::Success) {
+                return Err(Error::MaaControllerActionError("screencap".to_owned()));
+            }
+
+            let image = self.cached_image()?;
+            let frame = match roi {
+                Some(roi) => image.crop(roi)?.as_slice()?.to_vec(),
+                None => image.as_slice()?.to_vec(),
+            };
+
+            let now = Instant::now();
+            let same = previous
+                .as_ref()
+                .is_some_and(|previous| similarity(previous, &frame) >= threshold);
+            if !same {
+                stable_since = now;
+            } else if now - stable_since >= stable_for {
+                return Ok(());
+            }
+
+            if now - start >= timeout {
+                return Err(Error::MaaControllerNotStableError);
+            }
+            previous = Some(frame);
+        }
     }
 
     pub fn status(&self, id: MaaCtrlId) -> MaaResult<MaaStatus> {
@@ -351,15 +1166,207 @@ impl<T> MaaControllerInstance<T> {
         MaaStatus::try_from(status)
     }
 
+    /// Why the last connection attempt failed, as reported by MaaFramework, e.g. that adb could
+    /// not reach the device. Cleared by a successful connection.
+    ///
+    /// A custom controller also records here that it returned an empty frame from
+    /// `MaaCustomController::screencap`.
+    pub fn last_error(&self) -> Option<String> {
+        self.state.read().unwrap().last_error.clone()
+    }
+
     pub fn connected(&self) -> bool {
         unsafe { maa_bool!(internal::MaaControllerConnected(self.handle)) }
     }
+
+    /// The uuid of the connected device.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::Utf8Error] if the uuid is not valid UTF-8, see
+    /// [uuid_bytes](Self::uuid_bytes) to handle the encoding yourself.
+    pub fn uuid(&self) -> MaaResult<String> {
+        self.uuid_buffer()?.try_string()
+    }
+
+    /// The uuid of the connected device as raw bytes.
+    pub fn uuid_bytes(&self) -> MaaResult<Vec<u8>> {
+        Ok(self.uuid_buffer()?.bytes())
+    }
+
+    fn uuid_buffer(&self) -> MaaResult<MaaStringBuffer> {
+        let buffer = MaaStringBuffer::new();
+        let ret = unsafe { internal::MaaControllerGetUUID(self.handle, buffer.handle) };
+
+        if maa_bool!(ret) {
+            Ok(buffer)
+        } else {
+            Err(Error::MaaControllerActionError("get uuid".to_owned()))
+        }
+    }
 }
 
 impl<T> Drop for MaaControllerInstance<T> {
     fn drop(&mut self) {
-        unsafe {
-            internal::MaaControllerDestroy(self.handle);
+        if self.destroy_at_drop {
+            unsafe {
+                internal::MaaControllerDestroy(self.handle);
+            }
+        }
+
+        // the framework may call the callback until the controller is destroyed
+        for (ptr, drop_ptr) in [self.callback.take(), self.custom_controller.take()]
+            .into_iter()
+            .flatten()
+        {
+            unsafe { drop_ptr(ptr) };
+        }
+    }
+}
+
+/// Drop a box that was leaked with `Box::into_raw`, see [OwnedBox].
+#[cfg(any(
+    feature = "adb",
+    feature = "win32",
+    feature = "dbg",
+    feature = "custom_controller"
+))]
+unsafe fn drop_box<B>(ptr: *mut c_void) {
+    drop(Box::from_raw(ptr as *mut B));
+}
+
+/// A touch contact that is released when dropped, created by
+/// [MaaControllerInstance::touch_sequence].
+///
+/// If the sequence is dropped while the contact is down, e.g. on an early return or a panic, a
+/// touch up is posted so that the contact is not left pressed on the device.
+pub struct MaaTouchSequence<'a, T> {
+    controller: &'a MaaControllerInstance<T>,
+    contact: i32,
+    down: bool,
+}
+
+impl<'a, T> MaaTouchSequence<'a, T> {
+    pub fn down(&mut self, x: i32, y: i32, pressure: i32) -> MaaResult<MaaCtrlId> {
+        let id = self
+            .controller
+            .post_touch_down(self.contact, x, y, pressure)?;
+        self.down = true;
+        Ok(id)
+    }
+
+    pub fn move_to(&mut self, x: i32, y: i32, pressure: i32) -> MaaResult<MaaCtrlId> {
+        self.controller
+            .post_touch_move(self.contact, x, y, pressure)
+    }
+
+    pub fn up(&mut self) -> MaaResult<MaaCtrlId> {
+        self.down = false;
+        self.controller.post_touch_up(self.contact)
+    }
+
+    pub fn wait(&self, id: MaaCtrlId) -> MaaResult<MaaStatus> {
+        self.controller.wait(id)
+    }
+}
+
+impl<'a, T> Drop for MaaTouchSequence<'a, T> {
+    fn drop(&mut self) {
+        if self.down {
+            let _ = self.up();
+        }
+    }
+}
+
+/// Run the same kind of input on several controllers at once, e.g. for multi-device automation.
+///
+/// The input is posted to every controller before waiting on any of them, so the devices act at
+/// about the same time instead of one after the other.
+///
+/// ```ignore
+/// let mut barrier = Barrier::new();
+/// barrier.add(&phone_a).add(&phone_b);
+/// let statuses = barrier.click_all(&[(100, 200), (110, 205)])?;
+/// ```
+pub struct Barrier<'a, T> {
+    controllers: Vec<&'a MaaControllerInstance<T>>,
+}
+
+impl<'a, T> Default for Barrier<'a, T> {
+    fn default() -> Self {
+        Barrier {
+            controllers: Vec::new(),
+        }
+    }
+}
+
+impl<'a, T> Barrier<'a, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, controller: &'a MaaControllerInstance<T>) -> &mut Self {
+        self.controllers.push(controller);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.controllers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.controllers.is_empty()
+    }
+
+    /// Click `points[i]` on the `i`-th controller and wait for all the clicks.
+    ///
+    /// There must be exactly one point per controller. Returns the status of each click, in the
+    /// order the controllers were added.
+    pub fn click_all(&self, points: &[(i32, i32)]) -> MaaResult<Vec<MaaStatus>> {
+        if points.len() != self.controllers.len() {
+            return Err(Error::MaaControllerBarrierSizeError {
+                expected: self.controllers.len(),
+                actual: points.len(),
+            });
+        }
+
+        self.post_all(|i, controller| {
+            let (x, y) = points[i];
+            controller.post_click(x, y)
+        })
+    }
+
+    /// Post an input with `post` on each controller, given with its index, then wait for all of
+    /// them.
+    ///
+    /// If a post fails, the inputs already posted are still waited for before the error is
+    /// returned.
+    pub fn post_all<F>(&self, mut post: F) -> MaaResult<Vec<MaaStatus>>
+    where
+        F: FnMut(usize, &MaaControllerInstance<T>) -> MaaResult<MaaCtrlId>,
+    {
+        let mut ids = Vec::with_capacity(self.controllers.len());
+        let mut error = None;
+        for (i, controller) in self.controllers.iter().enumerate() {
+            match post(i, controller) {
+                Ok(id) => ids.push(id),
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let statuses = self
+            .controllers
+            .iter()
+            .zip(ids)
+            .map(|(controller, id)| controller.wait(id))
+            .collect::<MaaResult<Vec<_>>>();
+
+        match error {
+            Some(e) => Err(e),
+            None => statuses,
         }
     }
 }
@@ -415,3 +1422,31 @@ impl MaaControllerOption {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A controller whose handle was never created, like one that was destroyed.
+    fn dead_controller() -> MaaControllerInstance<()> {
+        MaaControllerInstance::new_from_handle(std::ptr::null_mut())
+    }
+
+    #[test]
+    fn post_to_dead_handle_fails() {
+        let controller = dead_controller();
+
+        assert!(matches!(
+            controller.post_click(0, 0),
+            Err(Error::MaaControllerPostError(_))
+        ));
+        assert!(matches!(
+            controller.post_press_key(ANDROID_KEYCODE_BACK),
+            Err(Error::MaaControllerPostError(_))
+        ));
+        assert!(matches!(
+            controller.post_screencap(),
+            Err(Error::MaaControllerPostError(_))
+        ));
+    }
+}
@@ -2,11 +2,16 @@ use serde::{Deserialize, Serialize};
 
 use crate::{error, internal};
 
+/// Mode of a debug controller, see
+/// [MaaControllerInstance::new_dbg](crate::controller::MaaControllerInstance::new_dbg).
 #[derive(Debug, Serialize, Deserialize, Default, Clone, Copy)]
 pub enum MaaDbgControllerType {
     #[default]
     Invalid,
+    /// Serve the images found at the read path as screenshots, one after another. Input is
+    /// accepted and ignored.
     CarouselImage,
+    /// Replay a recording made with the recording option, read from the read path.
     ReplayRecording,
 }
 
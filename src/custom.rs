@@ -29,3 +29,11 @@ pub mod custom_recognizer;
 #[cfg(feature = "custom_action")]
 #[doc(cfg(feature = "custom_action"))]
 pub mod custom_action;
+
+/// A custom component together with the instance it is registered to, so that its callbacks can
+/// reach the instance through [MaaSyncContext](crate::sync_context::MaaSyncContext).
+#[cfg(any(feature = "custom_recognizer", feature = "custom_action"))]
+pub(crate) struct Registered<C> {
+    pub(crate) component: C,
+    pub(crate) instance: crate::internal::MaaInstanceHandle,
+}
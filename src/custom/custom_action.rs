@@ -1,9 +1,21 @@
-use crate::{buffer::rect_buffer::MaaRectBuffer, internal, string, sync_context::MaaSyncContext};
+use crate::{
+    buffer::rect_buffer::MaaRectBuffer, custom::Registered, internal, string,
+    sync_context::MaaSyncContext,
+};
 
+/// A custom action.
+///
+/// `run` is called on a framework worker thread. `stop` may be called from another thread while
+/// `run` is in progress, so both take `&self` and any state they share needs interior
+/// mutability, e.g. an atomic flag that `stop` sets and `run` checks.
+///
+/// # Migrating from 0.5
+///
+/// `run` used to take `&mut self`. Move the state it mutates into a `Mutex` or atomics.
 #[allow(unused)]
 pub trait MaaCustomAction {
     fn run(
-        &mut self,
+        &self,
         sync_context: MaaSyncContext,
         task_name: String,
         custom_action_param: String,
@@ -12,7 +24,7 @@ pub trait MaaCustomAction {
     ) -> bool {
         false
     }
-    fn stop(&mut self) {}
+    fn stop(&self) {}
 }
 
 pub(crate) unsafe extern "C" fn maa_custom_action_run<A>(
@@ -26,8 +38,9 @@ pub(crate) unsafe extern "C" fn maa_custom_action_run<A>(
 where
     A: MaaCustomAction,
 {
-    let custom_action = &mut *(action as *mut A);
-    let sync_context = MaaSyncContext::from(sync_context);
+    let registered = &*(action as *const Registered<A>);
+    let custom_action = &registered.component;
+    let sync_context = MaaSyncContext::with_instance(sync_context, registered.instance);
     let task_name = string!(task_name);
     let custom_action_param = string!(custom_action_param);
     let cur_box = MaaRectBuffer::from(cur_box);
@@ -47,6 +60,6 @@ pub(crate) unsafe extern "C" fn maa_custom_action_stop<A>(action: internal::MaaT
 where
     A: MaaCustomAction,
 {
-    let custom_action = &mut *(action as *mut A);
+    let custom_action = &(*(action as *const Registered<A>)).component;
     custom_action.stop();
 }
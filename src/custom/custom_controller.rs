@@ -1,6 +1,10 @@
-use std::ffi::c_void;
+use std::{
+    ffi::c_void,
+    sync::{Arc, Mutex, RwLock},
+};
 
 use crate::{
+    controller::ControllerState,
     internal,
     string,
 };
@@ -30,6 +34,17 @@ pub trait MaaCustomController {
     /// # Return value
     ///
     /// (rows,cols,typ,data)
+    ///
+    /// `data` points to `rows` rows of `cols` pixels of OpenCV type `typ`, e.g. `CV_8UC3` for
+    /// BGR. It is copied before this returns, so it can point into a framebuffer kept by the
+    /// controller and reused for every frame.
+    ///
+    /// Return `None` if the screenshot could not be taken. A frame with no rows, no cols or a
+    /// null `data` also fails the screencap, instead of handing an empty image to the
+    /// recognitions. MaaFramework 1.8 only gets a success flag back, so it cannot tell the two
+    /// apart; an empty frame is recorded as the controller's
+    /// [last_error](crate::controller::MaaControllerInstance::last_error) instead, and logged as
+    /// a warning with the `log` feature.
     fn screencap(&mut self) -> Option<(i32, i32, i32, *mut c_void)> {
         None
     }
@@ -57,13 +72,70 @@ pub trait MaaCustomController {
     }
 }
 
+/// Keep access to a custom controller after handing it to
+/// [MaaControllerInstance::new_custom](crate::controller::MaaControllerInstance::new_custom).
+///
+/// Pass a clone of the `Arc` and read the state, e.g. counters, through the other one. Calls
+/// from the framework are serialized by the mutex.
+impl<C> MaaCustomController for Arc<Mutex<C>>
+where
+    C: MaaCustomController,
+{
+    fn connect(&mut self) -> bool {
+        self.lock().unwrap().connect()
+    }
+    fn request_uuid(&mut self) -> Option<String> {
+        self.lock().unwrap().request_uuid()
+    }
+    fn request_resolution(&mut self) -> Option<(i32, i32)> {
+        self.lock().unwrap().request_resolution()
+    }
+    fn start_app(&mut self, intent: String) -> bool {
+        self.lock().unwrap().start_app(intent)
+    }
+    fn stop_app(&mut self, intent: String) -> bool {
+        self.lock().unwrap().stop_app(intent)
+    }
+    fn screencap(&mut self) -> Option<(i32, i32, i32, *mut c_void)> {
+        self.lock().unwrap().screencap()
+    }
+    fn click(&mut self, x: i32, y: i32) -> bool {
+        self.lock().unwrap().click(x, y)
+    }
+    fn swipe(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, duration: i32) -> bool {
+        self.lock().unwrap().swipe(x1, y1, x2, y2, duration)
+    }
+    fn touch_down(&mut self, contact: i32, x: i32, y: i32, pressure: i32) -> bool {
+        self.lock().unwrap().touch_down(contact, x, y, pressure)
+    }
+    fn touch_move(&mut self, contact: i32, x: i32, y: i32, pressure: i32) -> bool {
+        self.lock().unwrap().touch_move(contact, x, y, pressure)
+    }
+    fn touch_up(&mut self, contact: i32) -> bool {
+        self.lock().unwrap().touch_up(contact)
+    }
+    fn press_key(&mut self, key: i32) -> bool {
+        self.lock().unwrap().press_key(key)
+    }
+    fn input_text(&mut self, text: String) -> bool {
+        self.lock().unwrap().input_text(text)
+    }
+}
+
+/// Transparent argument of a custom controller: the user controller and the state shared with the
+/// [MaaControllerInstance](crate::controller::MaaControllerInstance).
+pub(crate) struct CustomControllerArg<C> {
+    pub(crate) controller: C,
+    pub(crate) state: Arc<RwLock<ControllerState>>,
+}
+
 pub(crate) unsafe extern "C" fn custom_controller_connect<C>(
     controller: internal::MaaTransparentArg,
 ) -> internal::MaaBool
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     internal::MaaBool::from(controller.connect())
 }
 
@@ -74,12 +146,12 @@ pub(crate) unsafe extern "C" fn custom_controller_request_uuid<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     let ret = match controller.request_uuid() {
         Some(uuid) => {
             let uuid = internal::to_cstring(&uuid);
-            internal::MaaSetString(buffer, uuid);
-            true
+            let ret = internal::MaaSetString(buffer, uuid);
+            ret != 0
         }
         None => false,
     };
@@ -95,7 +167,7 @@ pub(crate) unsafe extern "C" fn custom_controller_request_resolution<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     let ret = match controller.request_resolution() {
         Some((w, h)) => {
             *width = w;
@@ -115,7 +187,7 @@ pub(crate) unsafe extern "C" fn custom_controller_start_app<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     let intent = string!(intent);
     internal::MaaBool::from(controller.start_app(intent))
 }
@@ -127,7 +199,7 @@ pub(crate) unsafe extern "C" fn custom_controller_stop_app<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     let intent = string!(intent);
     internal::MaaBool::from(controller.stop_app(intent))
 }
@@ -139,11 +211,21 @@ pub(crate) unsafe extern "C" fn custom_controller_screencap<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
-    let ret = match controller.screencap() {
+    let arg = &mut *(controller as *mut CustomControllerArg<C>);
+    let ret = match arg.controller.screencap() {
+        Some((rows, cols, _, data)) if rows <= 0 || cols <= 0 || data.is_null() => {
+            let why = format!(
+                "custom controller returned an empty frame: {rows}x{cols}, data {}",
+                if data.is_null() { "null" } else { "set" }
+            );
+            #[cfg(feature = "log")]
+            log::warn!("{why}");
+            arg.state.write().unwrap().last_error = Some(why);
+            false
+        }
         Some((rows, cols, typ, data)) => {
-            internal::MaaSetImageRawData(buffer, data, cols, rows, typ);
-            true
+            let ret = internal::MaaSetImageRawData(buffer, data, cols, rows, typ);
+            ret != 0
         }
         None => false,
     };
@@ -159,7 +241,7 @@ pub(crate) unsafe extern "C" fn custom_controller_click<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     internal::MaaBool::from(controller.click(x, y))
 }
 
@@ -174,7 +256,7 @@ pub(crate) unsafe extern "C" fn custom_controller_swipe<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     internal::MaaBool::from(controller.swipe(x1, y1, x2, y2, duration))
 }
 
@@ -188,7 +270,7 @@ pub(crate) unsafe extern "C" fn custom_controller_touch_down<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     internal::MaaBool::from(controller.touch_down(contact, x, y, pressure))
 }
 
@@ -202,7 +284,7 @@ pub(crate) unsafe extern "C" fn custom_controller_touch_move<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     internal::MaaBool::from(controller.touch_move(contact, x, y, pressure))
 }
 
@@ -213,7 +295,7 @@ pub(crate) unsafe extern "C" fn custom_controller_touch_up<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     internal::MaaBool::from(controller.touch_up(contact))
 }
 
@@ -224,7 +306,7 @@ pub(crate) unsafe extern "C" fn custom_controller_press_key<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     internal::MaaBool::from(controller.press_key(key))
 }
 
@@ -235,7 +317,7 @@ pub(crate) unsafe extern "C" fn custom_controller_input_text<C>(
 where
     C: MaaCustomController,
 {
-    let controller = &mut *(controller as *mut C);
+    let controller = &mut (*(controller as *mut CustomControllerArg<C>)).controller;
     let text = string!(text);
     internal::MaaBool::from(controller.input_text(text))
 }
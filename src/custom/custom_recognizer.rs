@@ -1,10 +1,16 @@
+use std::sync::{Arc, Mutex};
+
 use crate::{
     buffer::{image_buffer::MaaImageBuffer, rect_buffer::MaaRectBuffer},
+    custom::Registered,
     internal,
     string,
     sync_context::MaaSyncContext,
 };
 
+/// A custom recognizer.
+///
+/// `analyze` is called on a framework worker thread.
 #[allow(unused)]
 pub trait MaaCustomRecognizer {
     fn analyze(
@@ -19,6 +25,33 @@ pub trait MaaCustomRecognizer {
     }
 }
 
+/// Share one recognizer between several registrations.
+///
+/// Register clones of the same `Arc` under different names to back all of them with a single
+/// instance, e.g. one holding an expensive model. Calls from the framework are serialized by the
+/// mutex.
+impl<R> MaaCustomRecognizer for Arc<Mutex<R>>
+where
+    R: MaaCustomRecognizer,
+{
+    fn analyze(
+        &mut self,
+        sync_context: MaaSyncContext,
+        image: MaaImageBuffer,
+        task_name: String,
+        custom_recognition_param: String,
+        out_rect: MaaRectBuffer,
+    ) -> Option<String> {
+        self.lock().unwrap().analyze(
+            sync_context,
+            image,
+            task_name,
+            custom_recognition_param,
+            out_rect,
+        )
+    }
+}
+
 pub(crate) unsafe extern "C" fn custom_recognier_analyze<R>(
     sync_context: internal::MaaSyncContextHandle,
     image: internal::MaaImageBufferHandle,
@@ -31,11 +64,12 @@ pub(crate) unsafe extern "C" fn custom_recognier_analyze<R>(
 where
     R: MaaCustomRecognizer,
 {
-    let sync_context = MaaSyncContext::from(sync_context);
+    let registered = &mut *(recognizer as *mut Registered<R>);
+    let sync_context = MaaSyncContext::with_instance(sync_context, registered.instance);
     let image = MaaImageBuffer::from(image);
     let task_name = string!(task_name);
     let custom_recognition_param = string!(custom_recognition_param);
-    let recognizer = &mut *(recognizer as *mut R);
+    let recognizer = &mut registered.component;
     let out_box = MaaRectBuffer::from(out_box);
     match recognizer.analyze(
         sync_context,
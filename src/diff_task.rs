@@ -1,18 +1,22 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 use derive_builder::Builder;
-use serde::{ser::SerializeSeq, Serialize};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{ser::SerializeSeq, Deserialize, Serialize};
 use serde_json::Value;
 use serde_with::skip_serializing_none;
 
 #[derive(Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(untagged)]
 pub enum List<T: Debug + Clone + Serialize> {
     Single(T),
     Multiple(Vec<T>),
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum Recognition {
     DirectHit,
     TemplateMatch,
@@ -24,18 +28,24 @@ pub enum Recognition {
     Custom,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum Action {
     DoNothing,
     Click,
     Swipe,
     Key,
+    /// Type [DiffTask::input_text].
+    InputText,
     StartApp,
     StopApp,
+    /// Stop the running task.
+    StopTask,
     Custom,
 }
 
 #[derive(Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum Order {
     Horizontal,
     Vertical,
@@ -45,6 +55,7 @@ pub enum Order {
 }
 
 #[derive(Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum Detector {
     SIFT,
     KAZE,
@@ -54,6 +65,7 @@ pub enum Detector {
 }
 
 #[derive(Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(untagged)]
 pub enum WaitFreezes {
     Time(u32),
@@ -66,6 +78,80 @@ pub enum WaitFreezes {
     },
 }
 
+impl WaitFreezes {
+    /// Build a [WaitFreezes], only setting the fields that differ from the framework defaults.
+    ///
+    /// ```ignore
+    /// let wait = WaitFreezes::builder().time(500).threshold(0.98).build();
+    /// ```
+    pub fn builder() -> WaitFreezesBuilder {
+        WaitFreezesBuilder::default()
+    }
+}
+
+/// Builder of [WaitFreezes], created by [WaitFreezes::builder].
+///
+/// Builds the short [WaitFreezes::Time] form if only the time is set. Unset fields of the object
+/// form take the framework defaults: 1 ms, the current node's target, no offset, a threshold of
+/// 0.95 and method 5.
+#[derive(Debug, Clone, Default)]
+pub struct WaitFreezesBuilder {
+    time: Option<u32>,
+    target: Option<Target>,
+    target_offset: Option<[i32; 4]>,
+    threshold: Option<f32>,
+    method: Option<u32>,
+}
+
+impl WaitFreezesBuilder {
+    /// How long in milliseconds the target has to stay still.
+    pub fn time(mut self, time: u32) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    pub fn target_offset(mut self, target_offset: [i32; 4]) -> Self {
+        self.target_offset = Some(target_offset);
+        self
+    }
+
+    /// Template match score above which two frames are considered the same.
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// OpenCV template match method.
+    pub fn method(mut self, method: u32) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    pub fn build(self) -> WaitFreezes {
+        let time = self.time.unwrap_or(1);
+        if self.target.is_none()
+            && self.target_offset.is_none()
+            && self.threshold.is_none()
+            && self.method.is_none()
+        {
+            return WaitFreezes::Time(time);
+        }
+
+        WaitFreezes::Object {
+            time,
+            target: self.target.unwrap_or(Target::True),
+            target_offset: self.target_offset.unwrap_or_default(),
+            threshold: self.threshold.unwrap_or(0.95),
+            method: self.method.unwrap_or(5),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Target {
     True,
@@ -73,6 +159,30 @@ pub enum Target {
     Area([u32; 4]),
 }
 
+#[cfg(feature = "schema")]
+impl JsonSchema for Target {
+    fn schema_name() -> String {
+        "Target".to_owned()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{SchemaObject, SubschemaValidation};
+
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                one_of: Some(vec![
+                    gen.subschema_for::<bool>(),
+                    gen.subschema_for::<String>(),
+                    gen.subschema_for::<[u32; 4]>(),
+                ]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 impl Serialize for Target {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -93,6 +203,7 @@ impl Serialize for Target {
 }
 
 #[derive(Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(untagged)]
 pub enum Variant<T, S> {
     Left(T),
@@ -101,6 +212,7 @@ pub enum Variant<T, S> {
 
 #[skip_serializing_none]
 #[derive(Serialize, Default, Builder, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[builder(default)]
 pub struct DiffTask {
     pub recognition: Option<Recognition>,
@@ -128,7 +240,11 @@ pub struct DiffTask {
     pub count: Option<u32>,
     pub detector: Option<Detector>,
     pub ratio: Option<f32>,
+    /// Lower bound of the color match, one value per channel. A single range is a flat list, e.g.
+    /// `[0, 0, 0]`, several ranges are a list of lists matched with the same index of `upper`.
+    /// See [DiffTask::color_match].
     pub lower: Option<List<Vec<u32>>>,
+    /// Upper bound of the color match, shaped like `lower`.
     pub upper: Option<List<Vec<u32>>>,
     pub connected: Option<bool>,
     pub expected: Option<List<Variant<String, u32>>>,
@@ -151,3 +267,67 @@ pub struct DiffTask {
     pub custom_action: Option<String>,
     pub custom_action_param: Option<Value>,
 }
+
+impl DiffTask {
+    /// A `ColorMatch` recognition of the pixels between `lower` and `upper`, channel by channel.
+    pub fn color_match(lower: [u32; 3], upper: [u32; 3]) -> Self {
+        DiffTask {
+            recognition: Some(Recognition::ColorMatch),
+            lower: Some(List::Single(lower.to_vec())),
+            upper: Some(List::Single(upper.to_vec())),
+            ..Default::default()
+        }
+    }
+
+    /// A `ColorMatch` recognition of the pixels within any of the `(lower, upper)` ranges.
+    pub fn color_match_ranges(ranges: &[([u32; 3], [u32; 3])]) -> Self {
+        let (lower, upper) = ranges
+            .iter()
+            .map(|(lower, upper)| (lower.to_vec(), upper.to_vec()))
+            .unzip();
+
+        DiffTask {
+            recognition: Some(Recognition::ColorMatch),
+            lower: Some(List::Multiple(lower)),
+            upper: Some(List::Multiple(upper)),
+            ..Default::default()
+        }
+    }
+
+    /// Rough time the action of this node takes, from its delays, freeze waits and swipe
+    /// duration, with the framework defaults for unset fields.
+    ///
+    /// Recognition time and waiting for the node to be hit are not included.
+    pub fn estimated_duration(&self) -> Duration {
+        let wait_freezes = |wait: &Option<WaitFreezes>| match wait {
+            Some(WaitFreezes::Time(time)) | Some(WaitFreezes::Object { time, .. }) => *time,
+            None => 0,
+        };
+        let action = match self.action {
+            Some(Action::Swipe) => self.duration.unwrap_or(200),
+            _ => 0,
+        };
+
+        let millis = [
+            self.pre_delay.unwrap_or(200),
+            wait_freezes(&self.pre_wait_freezes),
+            action,
+            self.post_delay.unwrap_or(500),
+            wait_freezes(&self.post_wait_freezes),
+        ]
+        .into_iter()
+        .map(u64::from)
+        .sum();
+        Duration::from_millis(millis)
+    }
+}
+
+/// JSON Schema of a pipeline file, a map from node names to [DiffTask].
+///
+/// Point your editor at it to get validation and completion while writing pipelines.
+#[cfg(feature = "schema")]
+#[doc(cfg(feature = "schema"))]
+pub fn json_schema() -> Value {
+    let schema = schemars::schema_for!(std::collections::HashMap<String, DiffTask>);
+    serde_json::to_value(schema).unwrap()
+}
@@ -2,10 +2,13 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
+    common::Rect,
     controller::MaaControllerOption,
     instance::{MaaInstOption, MaaTaskId},
+    msg::MaaMsg,
     resource::MaaResOption,
     utility::MaaGlobalOption,
+    MaaResult,
 };
 
 #[derive(Error, Debug, Serialize, Deserialize)]
@@ -32,24 +35,72 @@ pub enum Error {
     #[error("MaaController fails to set option {0}")]
     MaaControllerSetOptionError(MaaControllerOption),
 
+    #[error("MaaController fails to connect")]
+    MaaControllerConnectError,
+
+    #[error("MaaController fails to post {0}")]
+    MaaControllerPostError(String),
+
+    #[error("MaaController fails to {0}")]
+    MaaControllerActionError(String),
+
+    #[error("MaaController fails to get image")]
+    MaaControllerGetImageError,
+
+    #[error("MaaController screen did not become stable in time")]
+    MaaControllerNotStableError,
+
+    #[error("MaaController barrier has {expected} controllers, got {actual} points")]
+    MaaControllerBarrierSizeError { expected: usize, actual: usize },
+
     #[error("MaaResource fails to set option {0}")]
     MaaResourceSetOptionError(MaaResOption),
 
     #[error("MaaInstance fails to set option {0}")]
     MaaInstanceSetOptionError(MaaInstOption),
 
+    #[error("MaaInstance is not inited")]
+    MaaInstanceInitError,
+
     #[error("MaaInstance fails to bind resource")]
     MaaInstanceBindResourceError,
 
     #[error("MaaInstance fails to bind controller")]
     MaaInstanceBindControllerError,
 
+    #[error("MaaInstance fails to post task {0}")]
+    MaaInstancePostTaskError(String),
+
+    #[error("MaaInstance fails to post recognition {0}")]
+    MaaInstancePostRecognitionError(String),
+
+    #[error("MaaInstance fails to post action {0}")]
+    MaaInstancePostActionError(String),
+
+    #[error("MaaInstance has no resource bound")]
+    MaaInstanceNoResourceError,
+
+    #[error("MaaInstance has no controller bound")]
+    MaaInstanceNoControllerError,
+
+    #[error("MaaInstance fails to validate task: {0}")]
+    MaaInstanceValidateTaskError(String),
+
+    #[error("MaaInstance task {0} did not finish in time")]
+    MaaInstanceWaitTimeoutError(MaaTaskId),
+
+    #[error("MaaInstance task {0} did not succeed")]
+    MaaInstanceTaskFailedError(MaaTaskId),
+
     #[error("MaaInstance fails to set task param {0}")]
     MaaInstanceSetTaskParamError(MaaTaskId),
 
     #[error("MaaInstance fails to stop")]
     MaaInstanceStopError,
 
+    #[error("MaaInstance tasks did not stop before shutdown timed out")]
+    MaaInstanceShutdownTimeoutError,
+
     #[error("MaaInstance fails to register custom recognizer {0}")]
     MaaInstanceRegisterCustomRecognizerError(String),
 
@@ -71,6 +122,9 @@ pub enum Error {
     #[error("MaaSyncContext fails to run task: {0}")]
     MaaSyncContextRunTaskError(String),
 
+    #[error("MaaSyncContext fails to post task: {0}")]
+    MaaSyncContextPostTaskError(String),
+
     #[error("MaaSyncContext fails to run recognizer: {0}")]
     MaaSyncContextRunRecognizerError(String),
 
@@ -104,6 +158,15 @@ pub enum Error {
     #[error("MaaSyncContext fails to get cached image")]
     MaaSyncContextCachedImageError,
 
+    #[error("MaaResource fails to post path {0}")]
+    MaaResourcePostPathError(String),
+
+    #[error("MaaResource fails to load {0}")]
+    MaaResourceLoadError(String),
+
+    #[error("MaaResource hash is {actual}, expected {expected}")]
+    MaaResourceHashMismatchError { expected: String, actual: String },
+
     #[error("MaaResource fails to get hash")]
     MaaResourceGetHashError,
 
@@ -113,6 +176,15 @@ pub enum Error {
     #[error("MaaResource fails to clear")]
     MaaResourceClearError,
 
+    #[error("Maa fails to query node detail {0}")]
+    MaaQueryNodeDetailError(i64),
+
+    #[error("Maa fails to query task detail {0}")]
+    MaaQueryTaskDetailError(i64),
+
+    #[error("Maa fails to query recognition detail {0}")]
+    MaaQueryRecognitionDetailError(i64),
+
     #[error("Maa fails to set string buffeer {0}")]
     MaaSetStringError(String),
 
@@ -125,11 +197,112 @@ pub enum Error {
     #[error("MaaToolkit fails to find device")]
     MaaToolkitPostFindDeviceError,
 
+    #[error("MaaToolkit found no device")]
+    MaaToolkitNoDeviceError,
+
+    #[error("No matching message arrived in time")]
+    MaaMsgWaitTimeoutError,
+
+    #[error("Region {0:?} is not within the image")]
+    MaaImageRoiOutOfBoundsError(Rect),
+
     #[error("Buffer operation failed.")]
     BufferError,
 
     #[error("(De)serialize error: {0}")]
     SerdeError(String),
+
+    #[error("IO error: {0}")]
+    IoError(String),
+
+    #[error("Invalid UTF-8: {0}")]
+    Utf8Error(String),
+
+    #[error("JSON5 error: {0}")]
+    Json5Error(String),
+
+    #[error("{context}: {source}")]
+    Context { context: String, source: Box<Error> },
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::IoError(e.to_string())
+    }
+}
+
+impl Error {
+    /// The error a failure message of the framework stands for, `None` if the message does not
+    /// report a failure.
+    ///
+    /// The reason of a failed connection is kept as the context of the error.
+    pub fn from_msg(msg: &MaaMsg) -> Option<Error> {
+        let error = match msg {
+            MaaMsg::ResourceLoadingFailed(resource) => {
+                Error::MaaResourceLoadError(resource.path.clone())
+            }
+            MaaMsg::ControllerConnectFailed(failed) => Error::Context {
+                context: failed.why.clone(),
+                source: Box::new(Error::MaaControllerConnectError),
+            },
+            MaaMsg::ControllerUUIDGetFailed
+            | MaaMsg::ControllerResolutionGetFailed
+            | MaaMsg::ControllerScreencapInitFailed
+            | MaaMsg::ControllerTouchInputInitFailed
+            | MaaMsg::ControllerKeyInputInitFailed => Error::MaaControllerConnectError,
+            MaaMsg::ControllerActionFailed(action) => {
+                Error::MaaControllerActionError(format!("run action {}", action.id))
+            }
+            MaaMsg::TaskFailed(task) => Error::MaaInstanceTaskFailedError(task.id.into()),
+            _ => return None,
+        };
+
+        Some(error)
+    }
+
+    /// Parse a message and its details as passed to callbacks, then see [Error::from_msg].
+    pub fn from_detail_json(msg: &str, details: &str) -> MaaResult<Option<Error>> {
+        Ok(Error::from_msg(&MaaMsg::from(msg, details)?))
+    }
+}
+
+/// Extension methods for [`MaaResult`].
+pub trait ResultExt<T> {
+    /// Wrap the error, if any, with a message describing what was being done.
+    ///
+    /// ```ignore
+    /// controller.post_connect().with_context("connecting to main emulator")?;
+    /// ```
+    fn with_context(self, context: &str) -> MaaResult<T>;
+}
+
+impl<T> ResultExt<T> for MaaResult<T> {
+    fn with_context(self, context: &str) -> MaaResult<T> {
+        self.map_err(|e| Error::Context {
+            context: context.to_owned(),
+            source: Box::new(e),
+        })
+    }
+}
+
+#[cfg(feature = "json5")]
+impl From<json5::Error> for Error {
+    fn from(e: json5::Error) -> Self {
+        let json5::Error::Message { msg, location } = e;
+        match location {
+            Some(location) => Error::Json5Error(format!(
+                "line {}, column {}: {}",
+                location.line, location.column, msg
+            )),
+            None => Error::Json5Error(msg),
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(e: std::string::FromUtf8Error) -> Self {
+        Error::Utf8Error(e.to_string())
+    }
 }
 
 impl From<serde_json::Error> for Error {
@@ -1,24 +1,37 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ffi::{c_void, CString},
     fmt::Display,
     ops::Deref,
+    path::Path,
     ptr::null_mut,
+    sync::Mutex,
+    time::{Duration, Instant},
 };
 
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "tokio")]
+use std::future::Future;
+#[cfg(feature = "tokio")]
 use tokio::task::JoinError;
 
 pub use internal::MaaTaskId;
 
 use crate::{
+    buffer::{image_buffer::MaaImageBuffer, string_buffer::MaaStringBuffer},
     CallbackHandler,
     controller::MaaControllerInstance,
+    diff_task::{DiffTask, Order, Target},
     error,
     internal,
+    json_comments::strip_json_comments,
     maa_bool,
-    MaaResult, MaaStatus, resource::MaaResourceInstance,
+    MaaIdExt, MaaResult, MaaStatus, resource::MaaResourceInstance,
+    utility::{
+        query_node_detail, query_recognition_detail, query_recognition_detail_with,
+        query_run_report, query_task_detail, NodeDetail, RecognitionDetail, RunReport,
+        TaskDetail,
+    },
 };
 #[cfg(feature = "custom_action")]
 use crate::custom::custom_action::{
@@ -26,6 +39,8 @@ use crate::custom::custom_action::{
 };
 #[cfg(feature = "custom_recognizer")]
 use crate::custom::custom_recognizer::{custom_recognier_analyze, MaaCustomRecognizer};
+#[cfg(any(feature = "custom_recognizer", feature = "custom_action"))]
+use crate::custom::Registered;
 
 pub trait TaskParam: Serialize {
     fn get_param(&self) -> String {
@@ -60,9 +75,59 @@ impl MaaInstOption {
     }
 }
 
+/// The component and its API table handed to MaaFramework, and how to drop the component.
+type RegisteredComponent = (*mut c_void, *mut c_void, unsafe fn(*mut c_void));
+
+/// Drop a component that was leaked with `Box::into_raw` when it was registered.
+#[cfg(any(feature = "custom_recognizer", feature = "custom_action"))]
+unsafe fn drop_component<C>(component: *mut c_void) {
+    drop(Box::from_raw(component as *mut C));
+}
+
+/// What dropping a [MaaInstance] does with tasks that are still running.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DropBehavior {
+    /// Destroy the instance right away, which may interrupt a task in the middle of an action.
+    ///
+    /// A warning is logged when this happens with the `log` feature.
+    #[default]
+    Abort,
+    /// Stop the tasks and wait for them to finish before destroying the instance.
+    ///
+    /// The wait is not bounded, so dropping blocks for as long as a task takes to stop. Use
+    /// [MaaInstance::shutdown] to give up after a timeout.
+    WaitThenDrop,
+}
+
+/// Where a registered custom component runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaaCustomComponentSource {
+    /// Registered in process through the `register_custom_*` methods of [MaaInstance].
+    Local,
+    /// Registered as an external executable through the toolkit.
+    Executor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaaCustomComponentInfo {
+    pub name: String,
+    pub source: MaaCustomComponentSource,
+}
+
+/// How often [MaaTaskResult::wait_timeout] checks the task status, unless changed with
+/// [MaaTaskResult::set_poll_interval].
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A posted task, returned by the `post_*` methods of [MaaInstance].
+///
+/// It cannot be built outside of this crate, since it carries settings such as the wait timeout
+/// along with the id.
+#[non_exhaustive]
 pub struct MaaTaskResult<'a, T> {
     pub task_id: MaaTaskId,
     pub instance: &'a MaaInstance<T>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) poll_interval: Duration,
 }
 
 impl<'a, T> MaaTaskResult<'a, T> {
@@ -70,21 +135,230 @@ impl<'a, T> MaaTaskResult<'a, T> {
         self.instance.task_status(self.task_id)
     }
 
+    /// Wait for the task to finish, or for the timeout set with [TaskBuilder::timeout] if any.
     pub fn wait(&self) -> MaaResult<MaaStatus> {
-        self.instance.wait_task(self.task_id)
+        match self.timeout {
+            Some(timeout) => self.wait_timeout(timeout),
+            None => self.instance.wait_task(self.task_id),
+        }
+    }
+
+    /// Wait at most `timeout` for the task to finish.
+    ///
+    /// The task keeps running if the timeout is hit.
+    pub fn wait_timeout(&self, timeout: Duration) -> MaaResult<MaaStatus> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let status = self.status()?;
+            if !matches!(status, MaaStatus::Pending | MaaStatus::Running) {
+                return Ok(status);
+            }
+            if Instant::now() >= deadline {
+                return Err(error::Error::MaaInstanceWaitTimeoutError(self.task_id));
+            }
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+
+    /// Set how often the status is checked while waiting with a timeout, 10ms by default.
+    ///
+    /// A longer interval saves CPU when waiting on long or many tasks, a shorter one returns
+    /// sooner once the task is done. Waits without a timeout block in the framework instead.
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
     }
 
     #[cfg(feature = "tokio")]
     #[doc(cfg(feature = "tokio"))]
     pub async fn wait_async(&self) -> Result<MaaResult<MaaStatus>, JoinError> {
-        tokio::spawn(async move {
-            self.instance.wait_task(self.task_id)
-        }).await
+        // the blocking wait runs on its own thread, which only gets the raw handle and id
+        let handle = self.instance.handle as usize;
+        let task_id = self.task_id;
+        let status = tokio::task::spawn_blocking(move || unsafe {
+            internal::MaaWaitTask(handle as internal::MaaInstanceHandle, task_id)
+        })
+        .await?;
+
+        Ok(self
+            .instance
+            .track_status(task_id, MaaStatus::try_from(status)))
     }
 
     pub fn set_task_param(&self, param: &str) -> MaaResult<()> {
         self.instance.set_task_param(self.task_id, param)
     }
+
+    /// See [query_run_report].
+    pub fn report(&self) -> MaaResult<RunReport> {
+        query_run_report(self.task_id)
+    }
+
+    /// The screenshot the last node of the task was recognized on, if the task failed.
+    ///
+    /// Waits for the task. Returns `None` if the task succeeded, ran no node, or the framework did
+    /// not keep the image. Images are only kept with debugging options such as
+    /// [MaaGlobalOption::SaveDraw](crate::utility::MaaGlobalOption::SaveDraw) on.
+    pub fn failure_screenshot(&self) -> MaaResult<Option<MaaImageBuffer>> {
+        if matches!(self.wait()?, MaaStatus::Success) {
+            return Ok(None);
+        }
+
+        let detail = query_task_detail(self.task_id)?;
+        let Some(&node_id) = detail.node_id_list.last() else {
+            return Ok(None);
+        };
+        let node = query_node_detail(node_id)?;
+        let recognition = query_recognition_detail_with(node.reco_id, false)?;
+        let raw = recognition.raw_image_lazy()?;
+
+        Ok((!raw.empty()).then_some(raw))
+    }
+
+    /// Wait for the task and query its detail.
+    pub fn get(&self) -> MaaResult<TaskDetail> {
+        self.wait()?;
+        query_task_detail(self.task_id)
+    }
+
+    /// Transform what [get](Self::get) returns, e.g. to extract a single field.
+    ///
+    /// The status and waiting stay those of this task.
+    ///
+    /// ```ignore
+    /// let nodes = instance
+    ///     .post_task("Entry", DiffTask::default())?
+    ///     .map(|detail| detail.node_id_list.len());
+    /// println!("ran {} nodes", nodes.get()?);
+    /// ```
+    pub fn map<U, F>(self, f: F) -> MaaMappedTaskResult<'a, T, F>
+    where
+        F: Fn(TaskDetail) -> U,
+    {
+        MaaMappedTaskResult { task: self, f }
+    }
+
+    /// Wait for the task, then post the next one with `f` if it succeeded.
+    ///
+    /// ```ignore
+    /// let status = instance
+    ///     .post_task("A", DiffTask::default())?
+    ///     .and_then(|_| instance.post_task("B", DiffTask::default()))?
+    ///     .wait()?;
+    /// ```
+    pub fn and_then<F>(self, f: F) -> MaaResult<MaaTaskResult<'a, T>>
+    where
+        F: FnOnce(TaskDetail) -> MaaResult<MaaTaskResult<'a, T>>,
+    {
+        let status = self.wait()?;
+        if !matches!(status, MaaStatus::Success) {
+            return Err(error::Error::MaaInstanceTaskFailedError(self.task_id));
+        }
+
+        f(query_task_detail(self.task_id)?)
+    }
+
+    /// Async version of [MaaTaskResult::and_then].
+    #[cfg(feature = "tokio")]
+    #[doc(cfg(feature = "tokio"))]
+    pub async fn and_then_async<F, Fut>(
+        self,
+        f: F,
+    ) -> Result<MaaResult<MaaTaskResult<'a, T>>, JoinError>
+    where
+        F: FnOnce(TaskDetail) -> Fut,
+        Fut: Future<Output = MaaResult<MaaTaskResult<'a, T>>>,
+    {
+        let status = match self.wait_async().await? {
+            Ok(status) => status,
+            Err(e) => return Ok(Err(e)),
+        };
+        if !matches!(status, MaaStatus::Success) {
+            return Ok(Err(error::Error::MaaInstanceTaskFailedError(self.task_id)));
+        }
+
+        match query_task_detail(self.task_id) {
+            Ok(detail) => Ok(f(detail).await),
+            Err(e) => Ok(Err(e)),
+        }
+    }
+}
+
+/// A task whose detail is transformed when fetched, created by [MaaTaskResult::map].
+///
+/// Derefs to the [MaaTaskResult] for the status and waiting.
+pub struct MaaMappedTaskResult<'a, T, F> {
+    task: MaaTaskResult<'a, T>,
+    f: F,
+}
+
+impl<'a, T, F> Deref for MaaMappedTaskResult<'a, T, F> {
+    type Target = MaaTaskResult<'a, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.task
+    }
+}
+
+impl<'a, T, F, U> MaaMappedTaskResult<'a, T, F>
+where
+    F: Fn(TaskDetail) -> U,
+{
+    /// Wait for the task, query its detail and transform it.
+    pub fn get(&self) -> MaaResult<U> {
+        self.task.get().map(&self.f)
+    }
+
+    /// Transform the result further.
+    pub fn map<V, G>(self, g: G) -> MaaMappedTaskResult<'a, T, impl Fn(TaskDetail) -> V>
+    where
+        G: Fn(U) -> V,
+    {
+        let f = self.f;
+        MaaMappedTaskResult {
+            task: self.task,
+            f: move |detail| g(f(detail)),
+        }
+    }
+}
+
+/// Builder for posting a task, created by [MaaInstance::task].
+///
+/// ```ignore
+/// let task = instance
+///     .task("Entry")
+///     .param(serde_json::json!({ "Entry": { "timeout": 5000 } }))
+///     .timeout(Duration::from_secs(30))
+///     .post()?;
+/// let status = task.wait()?;
+/// ```
+pub struct TaskBuilder<'a, T> {
+    instance: &'a MaaInstance<T>,
+    entry: String,
+    param: String,
+    timeout: Option<Duration>,
+}
+
+impl<'a, T> TaskBuilder<'a, T> {
+    /// Set the param overriding the pipeline for this task.
+    pub fn param<P>(mut self, param: P) -> Self
+    where
+        P: TaskParam,
+    {
+        self.param = param.get_param();
+        self
+    }
+
+    /// Make [MaaTaskResult::wait] give up after `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn post(self) -> MaaResult<MaaTaskResult<'a, T>> {
+        let mut result = self.instance.post_task_raw(&self.entry, &self.param)?;
+        result.timeout = self.timeout;
+        Ok(result)
+    }
 }
 
 /// The MaaInstance struct is the main entry point for the Maa library.
@@ -106,11 +380,17 @@ impl<'a, T> MaaTaskResult<'a, T> {
 /// [MaaInstance], [MaaResourceInstance] and [MaaControllerInstance] use the same mechanism to manage the lifetime of the underlying C++ object.
 /// That is, if the object is created from the Rust code (like `MaaInstance::new`), the object will be destroyed when it goes out of scope. In this case, it is your responsibility to ensure that the object is not used after it has been destroyed.
 /// If the object is created from the C++ code, then you will not have to worry about the lifetime of the object.
+///
+/// Dropping a [MaaInstance] does not wait for running tasks. Use [MaaInstance::shutdown] to let them stop first.
 #[derive(Debug)]
 pub struct MaaInstance<T> {
     pub(crate) handle: internal::MaaInstanceHandle,
-    registered_custom_recognizers: HashMap<String, (*mut c_void, *mut c_void)>,
-    registered_custom_actions: HashMap<String, (*mut c_void, *mut c_void)>,
+    registered_custom_recognizers: HashMap<String, RegisteredComponent>,
+    registered_custom_actions: HashMap<String, RegisteredComponent>,
+    pub(crate) registered_recognizer_executors: HashSet<String>,
+    pub(crate) registered_action_executors: HashSet<String>,
+    posted_tasks: Mutex<Vec<MaaTaskId>>,
+    drop_behavior: DropBehavior,
     _phantom: std::marker::PhantomData<T>,
 }
 
@@ -149,6 +429,10 @@ impl<T> MaaInstance<T> {
             handle,
             registered_custom_recognizers: HashMap::new(),
             registered_custom_actions: HashMap::new(),
+            registered_recognizer_executors: HashSet::new(),
+            registered_action_executors: HashSet::new(),
+            posted_tasks: Mutex::new(Vec::new()),
+            drop_behavior: DropBehavior::default(),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -162,10 +446,10 @@ impl<T> MaaInstance<T> {
             }
         };
 
-        if status != 0 {
-            Err(error::Error::MaaInstanceSetOptionError(option))
-        } else {
+        if maa_bool!(status) {
             Ok(())
+        } else {
+            Err(error::Error::MaaInstanceSetOptionError(option))
         }
     }
 
@@ -194,38 +478,296 @@ impl<T> MaaInstance<T> {
         maa_bool!(ret)
     }
 
-    pub fn post_task<P>(&self, entry: &str, param: P) -> MaaTaskResult<T>
+    /// Start building a task for `entry`, see [TaskBuilder].
+    pub fn task(&self, entry: &str) -> TaskBuilder<'_, T> {
+        TaskBuilder {
+            instance: self,
+            entry: entry.to_owned(),
+            param: "{}".to_owned(),
+            timeout: None,
+        }
+    }
+
+    pub fn post_task<P>(&self, entry: &str, param: P) -> MaaResult<MaaTaskResult<'_, T>>
     where
         P: TaskParam,
     {
-        let entry = CString::new(entry).unwrap();
-        let param = param.get_param();
+        self.post_task_raw(entry, &param.get_param())
+    }
+
+    /// Post a task and call `on_node` with the detail of each node as it finishes, in order.
+    ///
+    /// This blocks until the task is done and returns it finished. MaaFramework 1.8 has no node
+    /// events, so the task detail is polled every [DEFAULT_POLL_INTERVAL] while it runs.
+    pub fn post_task_with_progress<P, F>(
+        &self,
+        entry: &str,
+        param: P,
+        mut on_node: F,
+    ) -> MaaResult<MaaTaskResult<'_, T>>
+    where
+        P: TaskParam,
+        F: FnMut(NodeDetail),
+    {
+        let task = self.post_task(entry, param)?;
+
+        let mut reported = 0;
+        let mut report = |node_ids: &[i64]| -> MaaResult<()> {
+            for &node_id in node_ids.iter().skip(reported) {
+                on_node(query_node_detail(node_id)?);
+                reported += 1;
+            }
+            Ok(())
+        };
+
+        while matches!(task.status()?, MaaStatus::Pending | MaaStatus::Running) {
+            // the detail may not be recorded until the first node runs
+            if let Ok(detail) = query_task_detail(task.task_id) {
+                report(&detail.node_id_list)?;
+            }
+            std::thread::sleep(DEFAULT_POLL_INTERVAL);
+        }
+        report(&query_task_detail(task.task_id)?.node_id_list)?;
+
+        Ok(task)
+    }
+
+    /// Post a task with the param read from the JSON file at `path`.
+    ///
+    /// `//` and `/* */` comments are allowed in the file. It is parsed before posting, so a
+    /// malformed file is reported as an error here.
+    pub fn post_task_from_file(&self, entry: &str, path: &Path) -> MaaResult<MaaTaskResult<'_, T>> {
+        let content = std::fs::read_to_string(path)?;
+        let param: serde_json::Value = serde_json::from_str(&strip_json_comments(&content))?;
+        self.post_task(entry, param)
+    }
+
+    /// Post a task with a JSON5 param, which may have comments, trailing commas, unquoted keys
+    /// and single quoted strings.
+    ///
+    /// The param is converted to strict JSON before posting. Parse errors are reported as
+    /// [Error::Json5Error](error::Error::Json5Error) with the line and column.
+    #[cfg(feature = "json5")]
+    #[doc(cfg(feature = "json5"))]
+    pub fn post_task_json5(&self, entry: &str, param: &str) -> MaaResult<MaaTaskResult<'_, T>> {
+        let param: serde_json::Value = json5::from_str(param)?;
+        self.post_task(entry, param)
+    }
+
+    fn post_task_raw(&self, entry: &str, param: &str) -> MaaResult<MaaTaskResult<'_, T>> {
+        let entry_str = CString::new(entry).unwrap();
         let param = CString::new(param).unwrap();
-        let task_id = unsafe { internal::MaaPostTask(self.handle, entry.as_ptr(), param.as_ptr()) };
-        MaaTaskResult {
+        let task_id =
+            unsafe { internal::MaaPostTask(self.handle, entry_str.as_ptr(), param.as_ptr()) };
+
+        if !task_id.is_valid() {
+            return Err(error::Error::MaaInstancePostTaskError(entry.to_owned()));
+        }
+
+        self.track_posted(task_id);
+
+        Ok(MaaTaskResult {
             task_id,
             instance: self,
+            timeout: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        })
+    }
+
+    /// Check that a task could be posted, without running anything.
+    ///
+    /// `entry` and every task referenced through `next`, `timeout_next` or `runout_next` in
+    /// `param` must be defined either in the bound resource or in `param` itself. Tasks that are
+    /// only defined in the resource are not inspected, since the framework does not expose their
+    /// content.
+    pub fn validate_task<P>(&self, entry: &str, param: P) -> MaaResult<()>
+    where
+        P: TaskParam,
+    {
+        let param: HashMap<String, serde_json::Value> = serde_json::from_str(&param.get_param())?;
+
+        let res = unsafe { internal::MaaGetResource(self.handle) };
+        if res.is_null() {
+            return Err(error::Error::MaaInstanceNoResourceError);
+        }
+
+        let buffer = MaaStringBuffer::new();
+        let ret = unsafe { internal::MaaResourceGetTaskList(res, buffer.handle) };
+        if !maa_bool!(ret) {
+            return Err(error::Error::MaaResourceGetTaskListError);
         }
+
+        let mut known: HashSet<String> = serde_json::from_str(&buffer.string())?;
+        known.extend(param.keys().cloned());
+
+        if !known.contains(entry) {
+            return Err(error::Error::MaaInstanceValidateTaskError(format!(
+                "entry {entry} is not defined"
+            )));
+        }
+
+        for (name, task) in &param {
+            for key in ["next", "timeout_next", "runout_next"] {
+                let refs = match task.get(key) {
+                    Some(serde_json::Value::String(next)) => vec![next.as_str()],
+                    Some(serde_json::Value::Array(next)) => {
+                        next.iter().filter_map(|v| v.as_str()).collect()
+                    }
+                    _ => vec![],
+                };
+
+                if let Some(missing) = refs.into_iter().find(|r| !known.contains(*r)) {
+                    return Err(error::Error::MaaInstanceValidateTaskError(format!(
+                        "{name} references undefined task {missing} in {key}"
+                    )));
+                }
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn post_recognition(&self, entry: &str, param: &str) -> MaaTaskResult<T> {
-        let entry = CString::new(entry).unwrap();
+    pub fn post_recognition(&self, entry: &str, param: &str) -> MaaResult<MaaTaskResult<'_, T>> {
+        let entry_str = CString::new(entry).unwrap();
         let param = CString::new(param).unwrap();
-        let task_id = unsafe { internal::MaaPostRecognition(self.handle, entry.as_ptr(), param.as_ptr()) };
-        MaaTaskResult {
+        let task_id = unsafe {
+            internal::MaaPostRecognition(self.handle, entry_str.as_ptr(), param.as_ptr())
+        };
+
+        if !task_id.is_valid() {
+            return Err(error::Error::MaaInstancePostRecognitionError(
+                entry.to_owned(),
+            ));
+        }
+
+        self.track_posted(task_id);
+
+        Ok(MaaTaskResult {
             task_id,
             instance: self,
+            timeout: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        })
+    }
+
+    /// Post the recognition of `entry`, picking the `index`-th hit once sorted by `order_by`.
+    ///
+    /// This overrides `order_by` and `index` of the pipeline for this run only. All the hits can
+    /// be read back with [RecognitionDetail::all_boxes](crate::utility::RecognitionDetail::all_boxes).
+    pub fn post_recognition_ordered(
+        &self,
+        entry: &str,
+        order_by: Order,
+        index: u32,
+    ) -> MaaResult<MaaTaskResult<'_, T>> {
+        let diff = DiffTask {
+            order_by: Some(order_by),
+            index: Some(index),
+            ..Default::default()
+        };
+        let param = serde_json::json!({ entry: diff });
+        self.post_recognition(entry, &param.to_string())
+    }
+
+    /// Run the recognition of `entry` on the current screen and wait for it.
+    ///
+    /// Returns the detail of the recognition if it hit, `None` otherwise. The framework takes a
+    /// fresh screencap for it, it cannot recognize on the cached image of the controller.
+    pub fn recognize(&self, entry: &str, param: &str) -> MaaResult<Option<RecognitionDetail>> {
+        let controller = unsafe { internal::MaaGetController(self.handle) };
+        if controller.is_null() {
+            return Err(error::Error::MaaInstanceNoControllerError);
         }
+
+        let task = self.post_recognition(entry, param)?;
+        task.wait()?;
+
+        let detail = query_task_detail(task.task_id)?;
+        let Some(&node_id) = detail.node_id_list.last() else {
+            return Ok(None);
+        };
+        let node = query_node_detail(node_id)?;
+        let recognition = query_recognition_detail(node.reco_id)?;
+
+        Ok(recognition.hit.then_some(recognition))
     }
 
-    pub fn post_action(&self, entry: &str, param: &str) -> MaaTaskResult<T> {
-        let entry = CString::new(entry).unwrap();
+    pub fn post_action(&self, entry: &str, param: &str) -> MaaResult<MaaTaskResult<'_, T>> {
+        let entry_str = CString::new(entry).unwrap();
         let param = CString::new(param).unwrap();
-        let task_id = unsafe { internal::MaaPostAction(self.handle, entry.as_ptr(), param.as_ptr()) };
-        MaaTaskResult {
+        let task_id =
+            unsafe { internal::MaaPostAction(self.handle, entry_str.as_ptr(), param.as_ptr()) };
+
+        if !task_id.is_valid() {
+            return Err(error::Error::MaaInstancePostActionError(entry.to_owned()));
+        }
+
+        self.track_posted(task_id);
+
+        Ok(MaaTaskResult {
             task_id,
             instance: self,
+            timeout: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        })
+    }
+
+    /// Post the action of `entry` with its target set to the box hit by a previously run task.
+    ///
+    /// This is the same as writing `"target": "<task_name>"` in the pipeline, so the action reuses
+    /// the recognition result of `task_name` instead of a literal area.
+    pub fn post_action_on_task(
+        &self,
+        entry: &str,
+        task_name: &str,
+    ) -> MaaResult<MaaTaskResult<'_, T>> {
+        let diff = DiffTask {
+            target: Some(Target::Task(task_name.to_owned())),
+            ..Default::default()
+        };
+        let param = serde_json::json!({ entry: diff });
+        self.post_action(entry, &param.to_string())
+    }
+
+    /// Ids of the tasks posted through this instance that have not started yet, in posting order.
+    ///
+    /// Tasks posted with [MaaSyncContext::post_task](crate::sync_context::MaaSyncContext::post_task)
+    /// are not tracked and never listed here or by [current_task](Self::current_task).
+    pub fn pending_tasks(&self) -> MaaResult<Vec<MaaTaskId>> {
+        self.tasks_with(|status| matches!(status, MaaStatus::Pending))
+    }
+
+    /// Id of the task posted through this instance that is running, if any.
+    pub fn current_task(&self) -> MaaResult<Option<MaaTaskId>> {
+        Ok(self
+            .tasks_with(|status| matches!(status, MaaStatus::Running))?
+            .first()
+            .copied())
+    }
+
+    /// Ids of the posted tasks whose status matches, forgetting the ones that are done.
+    fn tasks_with<F>(&self, f: F) -> MaaResult<Vec<MaaTaskId>>
+    where
+        F: Fn(&MaaStatus) -> bool,
+    {
+        let mut posted = self.posted_tasks.lock().unwrap();
+        let mut matching = Vec::new();
+        let mut unfinished = Vec::with_capacity(posted.len());
+
+        for &task_id in posted.iter() {
+            // not task_status, which would lock the list again
+            let status =
+                MaaStatus::try_from(unsafe { internal::MaaTaskStatus(self.handle, task_id) })?;
+            if f(&status) {
+                matching.push(task_id);
+            }
+            if matches!(status, MaaStatus::Pending | MaaStatus::Running) {
+                unfinished.push(task_id);
+            }
         }
+
+        *posted = unfinished;
+        Ok(matching)
     }
 
     fn set_task_param(&self, task_id: MaaTaskId, param: &str) -> MaaResult<()> {
@@ -242,13 +784,41 @@ impl<T> MaaInstance<T> {
     fn task_status(&self, task_id: MaaTaskId) -> MaaResult<MaaStatus> {
         let status = unsafe { internal::MaaTaskStatus(self.handle, task_id) };
 
-        MaaStatus::try_from(status)
+        self.track_status(task_id, MaaStatus::try_from(status))
     }
 
     fn wait_task(&self, task_id: MaaTaskId) -> MaaResult<MaaStatus> {
         let status = unsafe { internal::MaaWaitTask(self.handle, task_id) };
 
-        MaaStatus::try_from(status)
+        self.track_status(task_id, MaaStatus::try_from(status))
+    }
+
+    /// Remember a newly posted task, forgetting the ones that are done.
+    fn track_posted(&self, task_id: MaaTaskId) {
+        let mut posted = self.posted_tasks.lock().unwrap();
+        posted.retain(|&posted| {
+            let status = unsafe { internal::MaaTaskStatus(self.handle, posted) };
+            matches!(
+                MaaStatus::try_from(status),
+                Ok(MaaStatus::Pending | MaaStatus::Running)
+            )
+        });
+        posted.push(task_id);
+    }
+
+    /// Forget a posted task once it is done, so that the list does not grow without bound.
+    fn track_status(
+        &self,
+        task_id: MaaTaskId,
+        status: MaaResult<MaaStatus>,
+    ) -> MaaResult<MaaStatus> {
+        if let Ok(MaaStatus::Success | MaaStatus::Failed) = status {
+            self.posted_tasks
+                .lock()
+                .unwrap()
+                .retain(|&posted| posted != task_id);
+        }
+        status
     }
 
     #[deprecated(note = "Use `running` instead")]
@@ -282,24 +852,78 @@ impl<T> MaaInstance<T> {
         }
     }
 
+    /// Set what dropping this instance does with running tasks, see [DropBehavior].
+    pub fn set_drop_behavior(&mut self, behavior: DropBehavior) {
+        self.drop_behavior = behavior;
+    }
+
+    /// Stop the running tasks and wait up to `timeout` for them to finish before destroying the
+    /// instance.
+    ///
+    /// Dropping a [MaaInstance] destroys it right away, which may interrupt a task in the middle
+    /// of an action. The instance is destroyed even if the tasks have not stopped in time, in
+    /// which case an error is returned. This never waits longer than `timeout`, whatever the
+    /// [DropBehavior].
+    pub fn shutdown(mut self, timeout: Duration) -> MaaResult<()> {
+        self.post_stop()?;
+
+        let deadline = Instant::now() + timeout;
+        while self.running() {
+            if Instant::now() >= deadline {
+                // the timeout is up, so do not let the drop wait again
+                self.drop_behavior = DropBehavior::Abort;
+                return Err(error::Error::MaaInstanceShutdownTimeoutError);
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        Ok(())
+    }
+
+    /// The resource bound to this instance. Dropping it does not destroy the resource.
     pub fn resource(&self) -> MaaResourceInstance<T> {
         let handle = unsafe { internal::MaaGetResource(self.handle) };
         MaaResourceInstance::new_from_handle(handle)
     }
 
+    /// The controller bound to this instance. Dropping it does not destroy the controller.
     pub fn controller(&self) -> MaaControllerInstance<T> {
         let handle = unsafe { internal::MaaGetController(self.handle) };
         MaaControllerInstance::new_from_handle(handle)
     }
 
+    /// List the custom recognizers registered on this instance and where each of them runs.
+    pub fn custom_recognizer_details(&self) -> Vec<MaaCustomComponentInfo> {
+        custom_component_details(
+            self.registered_custom_recognizers.keys(),
+            &self.registered_recognizer_executors,
+        )
+    }
+
+    /// List the custom actions registered on this instance and where each of them runs.
+    pub fn custom_action_details(&self) -> Vec<MaaCustomComponentInfo> {
+        custom_component_details(
+            self.registered_custom_actions.keys(),
+            &self.registered_action_executors,
+        )
+    }
+
+    /// Register a custom recognizer under `name`.
+    ///
+    /// The recognizer is moved to the framework worker thread and is only ever used from
+    /// framework threads, hence the `Send` and `Sync` bounds, the same as for
+    /// [register_custom_action](Self::register_custom_action).
     #[cfg(feature = "custom_recognizer")]
     #[doc(cfg(feature = "custom_recognizer"))]
     pub fn register_custom_recognizer<R>(&mut self, name: &str, recognizer: R) -> MaaResult<()>
     where
-        R: MaaCustomRecognizer,
+        R: MaaCustomRecognizer + Send + Sync + 'static,
     {
         let name_str = internal::to_cstring(name);
-        let recognizer = Box::new(recognizer);
+        let recognizer = Box::new(Registered {
+            component: recognizer,
+            instance: self.handle,
+        });
         let recognizer = Box::into_raw(recognizer) as *mut c_void;
 
         let recognizer_api = internal::MaaCustomRecognizerAPI {
@@ -309,8 +933,10 @@ impl<T> MaaInstance<T> {
         let recognizer_api = Box::new(recognizer_api);
         let recognizer_api = Box::into_raw(recognizer_api) as *mut c_void;
 
-        self.registered_custom_recognizers
-            .insert(name.to_owned(), (recognizer, recognizer_api));
+        self.registered_custom_recognizers.insert(
+            name.to_owned(),
+            (recognizer, recognizer_api, drop_component::<Registered<R>>),
+        );
 
         let ret = unsafe {
             internal::MaaRegisterCustomRecognizer(
@@ -335,14 +961,13 @@ impl<T> MaaInstance<T> {
     pub fn unregister_custom_recognizer(&mut self, name: &str) -> MaaResult<()> {
         let name_str = internal::to_cstring(name);
 
-        let (recognizer, recognizer_api) = self.registered_custom_recognizers.remove(name).unwrap();
+        let (recognizer, recognizer_api, drop_recognizer) =
+            self.registered_custom_recognizers.remove(name).unwrap();
 
         let ret = unsafe { internal::MaaUnregisterCustomRecognizer(self.handle, name_str) };
 
         unsafe {
-            drop(Box::from_raw(
-                recognizer as *mut Box<dyn MaaCustomRecognizer>,
-            ));
+            drop_recognizer(recognizer);
             drop(Box::from_raw(
                 recognizer_api as *mut internal::MaaCustomRecognizerAPI,
             ));
@@ -366,11 +991,11 @@ impl<T> MaaInstance<T> {
             return Err(error::Error::MaaInstanceClearCustomRecognizerError);
         }
 
-        for (_name, (recognizer, recognizer_api)) in self.registered_custom_recognizers.drain() {
+        for (_name, (recognizer, recognizer_api, drop_recognizer)) in
+            self.registered_custom_recognizers.drain()
+        {
             unsafe {
-                drop(Box::from_raw(
-                    recognizer as *mut Box<dyn MaaCustomRecognizer>,
-                ));
+                drop_recognizer(recognizer);
                 drop(Box::from_raw(
                     recognizer_api as *mut internal::MaaCustomRecognizerAPI,
                 ));
@@ -380,14 +1005,21 @@ impl<T> MaaInstance<T> {
         Ok(())
     }
 
+    /// Register a custom action under `name`.
+    ///
+    /// The action runs on the framework worker thread and may be stopped from another one, hence
+    /// the `Send` and `Sync` bounds.
     #[cfg(feature = "custom_action")]
     #[doc(cfg(feature = "custom_action"))]
     pub fn register_custom_action<A>(&mut self, name: &str, action: A) -> MaaResult<()>
     where
-        A: MaaCustomAction,
+        A: MaaCustomAction + Send + Sync + 'static,
     {
         let name_str = internal::to_cstring(name);
-        let action = Box::new(action);
+        let action = Box::new(Registered {
+            component: action,
+            instance: self.handle,
+        });
         let action = Box::into_raw(action) as *mut c_void;
 
         let action_api = internal::MaaCustomActionAPI {
@@ -398,8 +1030,10 @@ impl<T> MaaInstance<T> {
         let action_api = Box::new(action_api);
         let action_api = Box::into_raw(action_api) as *mut c_void;
 
-        self.registered_custom_actions
-            .insert(name.to_owned(), (action, action_api));
+        self.registered_custom_actions.insert(
+            name.to_owned(),
+            (action, action_api, drop_component::<Registered<A>>),
+        );
 
         let ret = unsafe {
             internal::MaaRegisterCustomAction(self.handle, name_str, action_api.cast(), action)
@@ -419,12 +1053,13 @@ impl<T> MaaInstance<T> {
     pub fn unregister_custom_action(&mut self, name: &str) -> MaaResult<()> {
         let name_str = internal::to_cstring(name);
 
-        let (action, action_api) = self.registered_custom_actions.remove(name).unwrap();
+        let (action, action_api, drop_action) =
+            self.registered_custom_actions.remove(name).unwrap();
 
         let ret = unsafe { internal::MaaUnregisterCustomAction(self.handle, name_str) };
 
         unsafe {
-            drop(Box::from_raw(action as *mut Box<dyn MaaCustomAction>));
+            drop_action(action);
             drop(Box::from_raw(
                 action_api as *mut internal::MaaCustomActionAPI,
             ));
@@ -448,9 +1083,9 @@ impl<T> MaaInstance<T> {
             return Err(error::Error::MaaInstanceClearCustomActionError);
         }
 
-        for (_name, (action, action_api)) in self.registered_custom_actions.drain() {
+        for (_name, (action, action_api, drop_action)) in self.registered_custom_actions.drain() {
             unsafe {
-                drop(Box::from_raw(action as *mut Box<dyn MaaCustomAction>));
+                drop_action(action);
                 drop(Box::from_raw(
                     action_api as *mut internal::MaaCustomActionAPI,
                 ));
@@ -461,8 +1096,37 @@ impl<T> MaaInstance<T> {
     }
 }
 
+fn custom_component_details<'a>(
+    local: impl Iterator<Item = &'a String>,
+    executors: &HashSet<String>,
+) -> Vec<MaaCustomComponentInfo> {
+    let local = local.map(|name| MaaCustomComponentInfo {
+        name: name.clone(),
+        source: MaaCustomComponentSource::Local,
+    });
+    let executors = executors.iter().map(|name| MaaCustomComponentInfo {
+        name: name.clone(),
+        source: MaaCustomComponentSource::Executor,
+    });
+
+    local.chain(executors).collect()
+}
+
 impl<T> Drop for MaaInstance<T> {
     fn drop(&mut self) {
+        if self.running() {
+            match self.drop_behavior {
+                DropBehavior::Abort => {
+                    #[cfg(feature = "log")]
+                    log::warn!("MaaInstance dropped while running, the tasks are aborted");
+                }
+                DropBehavior::WaitThenDrop => {
+                    // nothing left to report the error to
+                    let _ = self.stop();
+                }
+            }
+        }
+
         unsafe {
             internal::MaaDestroy(self.handle);
         }
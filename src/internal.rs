@@ -34,16 +34,36 @@ macro_rules! maa_bool {
     };
 }
 
+#[macro_export]
+macro_rules! maa_id {
+    ($v:expr, $e:ident) => {
+        if $crate::MaaIdExt::is_valid(&$v) {
+            Ok($v)
+        } else {
+            Err(Error::$e)
+        }
+    };
+
+    ($v:expr, $e:ident, $c:expr) => {
+        if $crate::MaaIdExt::is_valid(&$v) {
+            Ok($v)
+        } else {
+            Err(Error::$e($c))
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! string {
-    ($string_view:expr) => {
+    ($string_view:expr) => {{
+        let string_view = $string_view;
         unsafe {
-            std::ffi::CStr::from_ptr($string_view)
+            std::ffi::CStr::from_ptr(string_view)
                 .to_str()
                 .unwrap()
                 .to_string()
         }
-    };
+    }};
 }
 
 #[allow(clippy::unwrap_used)]
@@ -0,0 +1,93 @@
+//! Comment tolerant JSON, like the framework accepts in pipeline and param files.
+
+/// Remove `//` and `/* */` comments outside of strings from JSON text.
+///
+/// An unterminated block comment runs to the end of the text.
+pub(crate) fn strip_json_comments(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push(c);
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_json_comments;
+
+    #[test]
+    fn line_comments() {
+        let json = "{\n  \"a\": 1, // one\n  \"b\": 2\n}";
+        assert_eq!(strip_json_comments(json), "{\n  \"a\": 1, \n  \"b\": 2\n}");
+    }
+
+    #[test]
+    fn block_comments_across_lines() {
+        let json = "{ /* first\n second **/ \"a\": 1 }";
+        assert_eq!(strip_json_comments(json), "{  \"a\": 1 }");
+    }
+
+    #[test]
+    fn comment_markers_in_strings() {
+        let json = r#"{ "url": "http://host/*path*/", "c": "//" }"#;
+        assert_eq!(strip_json_comments(json), json);
+    }
+
+    #[test]
+    fn escaped_quotes() {
+        let json = r#"{ "a": "say \"hi\" // not a comment", "b": "\\" } // comment"#;
+        assert_eq!(
+            strip_json_comments(json),
+            r#"{ "a": "say \"hi\" // not a comment", "b": "\\" } "#
+        );
+    }
+
+    #[test]
+    fn unterminated_comments() {
+        assert_eq!(strip_json_comments("{ \"a\": 1 } // end"), "{ \"a\": 1 } ");
+        assert_eq!(strip_json_comments("{ \"a\": 1 /* open"), "{ \"a\": 1 ");
+    }
+
+    #[test]
+    fn lone_slash_is_kept() {
+        assert_eq!(strip_json_comments("1 / 2"), "1 / 2");
+    }
+}
@@ -26,6 +26,9 @@
 //! - `custom_controller`: Enable custom controller for MaaFramework.
 //! - `custom_action`: Enable custom action for MaaFramework.
 //! - `custom`: Enable all custom features for MaaFramework.
+//! - `schema`: Enable JSON Schema export of the pipeline format with `schemars`. Not enabled by default.
+//! - `log`: Enable [msg::LogHandler], which forwards framework messages to the `log` crate. Not enabled by default.
+//! - `json5`: Enable [instance::MaaInstance::post_task_json5], which accepts JSON5 task params. Not enabled by default.
 //!
 //! The default features include all features so you might want to disable some of them if you don't need them.
 
@@ -52,18 +55,37 @@ pub mod custom;
 pub mod sync_context;
 
 pub mod buffer;
+pub mod common;
 pub mod controller;
 pub mod diff_task;
 pub mod error;
 pub mod instance;
+mod json_comments;
+pub mod lint;
 pub mod msg;
 pub mod resource;
+pub mod session;
 pub mod utility;
 
 use error::Error;
 
 pub type MaaResult<T> = Result<T, error::Error>;
 
+pub use internal::MaaId;
+
+/// Helpers for the ids returned by MaaFramework.
+pub trait MaaIdExt {
+    /// Whether the id refers to a posted operation, i.e. it is not
+    /// [INVALID_MAA_ID](common::INVALID_MAA_ID).
+    fn is_valid(&self) -> bool;
+}
+
+impl MaaIdExt for MaaId {
+    fn is_valid(&self) -> bool {
+        *self != common::INVALID_MAA_ID
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum MaaStatus {
     Invalid,
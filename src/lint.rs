@@ -0,0 +1,418 @@
+//! Check the pipeline of a resource bundle without a device, e.g. in CI.
+//!
+//! MaaFramework 1.8 cannot read node definitions back from a loaded resource, so the pipeline
+//! files are read directly. Comments are allowed in them, like the framework does.
+//!
+//! ```ignore
+//! let issues = maa_framework::lint::lint_resource(Path::new("resource"));
+//! for issue in &issues {
+//!     eprintln!("{}: {:?} {:?}", issue.file.display(), issue.node, issue.kind);
+//! }
+//! assert!(issues.is_empty());
+//! ```
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    diff_task::{Action, Recognition},
+    json_comments::strip_json_comments,
+};
+
+/// Fields holding the names of the nodes to run next.
+const NEXT_FIELDS: &[&str] = &["next", "timeout_next", "runout_next"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintIssue {
+    pub file: PathBuf,
+    /// `None` for issues about the whole file.
+    pub node: Option<String>,
+    pub kind: LintIssueKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LintIssueKind {
+    /// The file or directory could not be read or is not a JSON object.
+    Unreadable(String),
+    /// The node is also defined in another file.
+    DuplicateNode {
+        other_file: PathBuf,
+    },
+    /// A `next`, `timeout_next` or `runout_next` entry names a node defined nowhere.
+    UndefinedNode {
+        field: String,
+        target: String,
+    },
+    UnknownRecognition(String),
+    UnknownAction(String),
+    UnregisteredCustomRecognition(String),
+    UnregisteredCustomAction(String),
+    /// A ROI that is not `[x, y, w, h]` with non negative values.
+    InvalidRoi(Value),
+}
+
+/// Lint the pipeline of the bundle at `path`, without checking custom component names.
+pub fn lint_resource(path: &Path) -> Vec<LintIssue> {
+    lint_resource_with(path, None, None)
+}
+
+/// Lint the pipeline of the bundle at `path`.
+///
+/// If given, `custom_recognizers` and `custom_actions` are the names that will be registered,
+/// and custom nodes using any other name are reported.
+pub fn lint_resource_with(
+    path: &Path,
+    custom_recognizers: Option<&[&str]>,
+    custom_actions: Option<&[&str]>,
+) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    let mut files = Vec::new();
+    let pipeline_dir = path.join("pipeline");
+    if let Err(e) = collect_json_files(&pipeline_dir, &mut files) {
+        issues.push(LintIssue {
+            file: pipeline_dir,
+            node: None,
+            kind: LintIssueKind::Unreadable(e.to_string()),
+        });
+        return issues;
+    }
+    files.sort();
+
+    let mut nodes: Vec<(PathBuf, String, Map<String, Value>)> = Vec::new();
+    let mut defined: HashMap<String, PathBuf> = HashMap::new();
+    for file in files {
+        let pipeline = match read_pipeline(&file) {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                issues.push(LintIssue {
+                    file,
+                    node: None,
+                    kind: LintIssueKind::Unreadable(e),
+                });
+                continue;
+            }
+        };
+
+        for (name, node) in pipeline {
+            if let Some(other_file) = defined.get(&name) {
+                issues.push(LintIssue {
+                    file: file.clone(),
+                    node: Some(name.clone()),
+                    kind: LintIssueKind::DuplicateNode {
+                        other_file: other_file.clone(),
+                    },
+                });
+            } else {
+                defined.insert(name.clone(), file.clone());
+            }
+
+            match node {
+                Value::Object(node) => nodes.push((file.clone(), name, node)),
+                _ => issues.push(LintIssue {
+                    file: file.clone(),
+                    node: Some(name),
+                    kind: LintIssueKind::Unreadable("node is not an object".to_owned()),
+                }),
+            }
+        }
+    }
+
+    for (file, name, node) in &nodes {
+        let mut issue = |kind| {
+            issues.push(LintIssue {
+                file: file.clone(),
+                node: Some(name.clone()),
+                kind,
+            })
+        };
+
+        for &field in NEXT_FIELDS {
+            let targets = match node.get(field) {
+                Some(Value::String(target)) => vec![target.as_str()],
+                Some(Value::Array(targets)) => targets.iter().filter_map(Value::as_str).collect(),
+                _ => continue,
+            };
+            for target in targets {
+                if !defined.contains_key(target) {
+                    issue(LintIssueKind::UndefinedNode {
+                        field: field.to_owned(),
+                        target: target.to_owned(),
+                    });
+                }
+            }
+        }
+
+        let recognition = node.get("recognition").and_then(Value::as_str);
+        if let Some(recognition) = recognition {
+            if !is_variant::<Recognition>(recognition) {
+                issue(LintIssueKind::UnknownRecognition(recognition.to_owned()));
+            }
+        }
+        if let (Some("Custom"), Some(registered)) = (recognition, custom_recognizers) {
+            let custom = node
+                .get("custom_recognition")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if !registered.contains(&custom) {
+                issue(LintIssueKind::UnregisteredCustomRecognition(
+                    custom.to_owned(),
+                ));
+            }
+        }
+
+        let action = node.get("action").and_then(Value::as_str);
+        if let Some(action) = action {
+            if !is_variant::<Action>(action) {
+                issue(LintIssueKind::UnknownAction(action.to_owned()));
+            }
+        }
+        if let (Some("Custom"), Some(registered)) = (action, custom_actions) {
+            let custom = node
+                .get("custom_action")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if !registered.contains(&custom) {
+                issue(LintIssueKind::UnregisteredCustomAction(custom.to_owned()));
+            }
+        }
+
+        if let Some(roi) = node.get("roi") {
+            if !valid_roi(roi) {
+                issue(LintIssueKind::InvalidRoi(roi.clone()));
+            }
+        }
+    }
+
+    issues
+}
+
+fn collect_json_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_json_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_pipeline(file: &Path) -> Result<Map<String, Value>, String> {
+    let content = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+    match serde_json::from_str(&strip_json_comments(&content)) {
+        Ok(Value::Object(pipeline)) => Ok(pipeline),
+        Ok(_) => Err("pipeline is not an object".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Whether `name` is the name of a variant of `E`, as written in pipeline files.
+fn is_variant<E: DeserializeOwned>(name: &str) -> bool {
+    serde_json::from_value::<E>(Value::String(name.to_owned())).is_ok()
+}
+
+/// A single `[x, y, w, h]` or a list of them, all non negative.
+fn valid_roi(roi: &Value) -> bool {
+    let Some(values) = roi.as_array() else {
+        return false;
+    };
+
+    if values.iter().all(Value::is_array) {
+        return !values.is_empty() && values.iter().all(valid_roi);
+    }
+
+    values.len() == 4 && values.iter().all(|v| v.as_i64().is_some_and(|v| v >= 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// A bundle in a fresh temporary directory, removed on drop.
+    struct Bundle(PathBuf);
+
+    impl Bundle {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "maa-framework-lint-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = std::fs::remove_dir_all(&path);
+            for (file, content) in files {
+                let file = path.join("pipeline").join(file);
+                std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+                std::fs::write(file, content).unwrap();
+            }
+            Bundle(path)
+        }
+    }
+
+    impl Drop for Bundle {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn kinds(issues: &[LintIssue]) -> Vec<&LintIssueKind> {
+        issues.iter().map(|issue| &issue.kind).collect()
+    }
+
+    #[test]
+    fn valid_bundle() {
+        let bundle = Bundle::new(
+            "valid",
+            &[(
+                "main.json",
+                r#"{
+                    // comments are allowed
+                    "A": { "recognition": "OCR", "roi": [0, 0, 10, 10], "next": ["B"] },
+                    "B": { "action": "Click", "roi": [[0, 0, 1, 1], [2, 2, 1, 1]], "next": "C" },
+                    "C": { "action": "InputText", "input_text": "hello", "next": "D" },
+                    "D": { "action": "StopTask" }
+                }"#,
+            )],
+        );
+
+        assert!(lint_resource(&bundle.0).is_empty());
+    }
+
+    #[test]
+    fn missing_pipeline() {
+        let bundle = Bundle::new("missing", &[]);
+
+        let issues = lint_resource(&bundle.0);
+        assert!(matches!(kinds(&issues)[..], [LintIssueKind::Unreadable(_)]));
+    }
+
+    #[test]
+    fn unreadable_file() {
+        let bundle = Bundle::new("unreadable", &[("bad.json", "{ \"A\": ")]);
+
+        let issues = lint_resource(&bundle.0);
+        assert!(matches!(kinds(&issues)[..], [LintIssueKind::Unreadable(_)]));
+        assert!(issues[0].node.is_none());
+    }
+
+    #[test]
+    fn duplicate_node() {
+        let bundle = Bundle::new(
+            "duplicate",
+            &[
+                ("a.json", r#"{ "A": {} }"#),
+                ("sub/b.json", r#"{ "A": {} }"#),
+            ],
+        );
+
+        let issues = lint_resource(&bundle.0);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].node.as_deref(), Some("A"));
+        assert!(issues[0].file.ends_with("sub/b.json"));
+        assert!(matches!(
+            &issues[0].kind,
+            LintIssueKind::DuplicateNode { other_file } if other_file.ends_with("a.json")
+        ));
+    }
+
+    #[test]
+    fn undefined_next() {
+        let bundle = Bundle::new(
+            "undefined",
+            &[(
+                "main.json",
+                r#"{ "A": { "next": "B", "timeout_next": ["A", "C"], "runout_next": [] } }"#,
+            )],
+        );
+
+        let issues = lint_resource(&bundle.0);
+        let targets: Vec<_> = issues
+            .iter()
+            .map(|issue| match &issue.kind {
+                LintIssueKind::UndefinedNode { field, target } => (field.as_str(), target.as_str()),
+                kind => panic!("unexpected issue {kind:?}"),
+            })
+            .collect();
+        assert_eq!(targets, [("next", "B"), ("timeout_next", "C")]);
+    }
+
+    #[test]
+    fn unknown_recognition_and_action() {
+        let bundle = Bundle::new(
+            "unknown",
+            &[(
+                "main.json",
+                r#"{ "A": { "recognition": "Magic", "action": "Teleport" } }"#,
+            )],
+        );
+
+        let issues = lint_resource(&bundle.0);
+        assert!(matches!(
+            kinds(&issues)[..],
+            [
+                LintIssueKind::UnknownRecognition(recognition),
+                LintIssueKind::UnknownAction(action),
+            ] if recognition == "Magic" && action == "Teleport"
+        ));
+    }
+
+    #[test]
+    fn custom_names() {
+        let bundle = Bundle::new(
+            "custom",
+            &[(
+                "main.json",
+                r#"{
+                    "A": { "recognition": "Custom", "custom_recognition": "Known" },
+                    "B": { "recognition": "Custom", "custom_recognition": "Other" },
+                    "C": { "action": "Custom", "custom_action": "Other" }
+                }"#,
+            )],
+        );
+
+        assert!(lint_resource(&bundle.0).is_empty());
+
+        let issues = lint_resource_with(&bundle.0, Some(&["Known"]), Some(&[]));
+        assert!(matches!(
+            kinds(&issues)[..],
+            [
+                LintIssueKind::UnregisteredCustomRecognition(recognition),
+                LintIssueKind::UnregisteredCustomAction(action),
+            ] if recognition == "Other" && action == "Other"
+        ));
+    }
+
+    #[test]
+    fn invalid_roi() {
+        let bundle = Bundle::new(
+            "roi",
+            &[(
+                "main.json",
+                r#"{
+                    "A": { "roi": [0, 0, 10] },
+                    "B": { "roi": [0, -1, 10, 10] },
+                    "C": { "roi": [] },
+                    "D": { "roi": [[0, 0, 1, 1], [0, 0]] }
+                }"#,
+            )],
+        );
+
+        let issues = lint_resource(&bundle.0);
+        let nodes: Vec<_> = issues
+            .iter()
+            .filter_map(|issue| issue.node.as_deref())
+            .collect();
+        assert_eq!(nodes, ["A", "B", "C", "D"]);
+        assert!(issues
+            .iter()
+            .all(|issue| matches!(issue.kind, LintIssueKind::InvalidRoi(_))));
+    }
+}
@@ -1,7 +1,15 @@
+use std::{
+    sync::{
+        mpsc::{channel, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::MaaResult;
+use crate::{error::Error, utility::detail_candidates, CallbackHandler, MaaResult};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MaaMsgResource {
@@ -58,6 +66,16 @@ pub struct MaaMsgTaskFocus {
     pub status: String,
 }
 
+impl MaaMsgTaskFocus {
+    /// The recognition of the task parsed from [recognition](Self::recognition), `None` if the
+    /// message does not carry one.
+    ///
+    /// This saves querying the recognition detail, e.g. to draw a live overlay of the hits.
+    pub fn recognition_detail(&self) -> Option<MaaMsgRecognition> {
+        MaaMsgRecognition::parse(&self.recognition)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MaaMsgTaskDebug {
     pub id: i32,
@@ -71,6 +89,63 @@ pub struct MaaMsgTaskDebug {
     pub status: String,
 }
 
+impl MaaMsgTaskDebug {
+    /// See [MaaMsgTaskFocus::recognition_detail].
+    pub fn recognition_detail(&self) -> Option<MaaMsgRecognition> {
+        MaaMsgRecognition::parse(&self.recognition)
+    }
+}
+
+/// The recognition embedded in [MaaMsgTaskFocus] and [MaaMsgTaskDebug], parsed from their
+/// `recognition` field.
+///
+/// Every field is optional since what the framework includes depends on the message and on
+/// whether debug mode is on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MaaMsgRecognition {
+    pub id: Option<i64>,
+    pub hit: Option<bool>,
+    /// `[x, y, width, height]`.
+    #[serde(rename = "box")]
+    pub hit_box: Option<[i32; 4]>,
+    /// Algorithm specific detail, like
+    /// [RecognitionDetail::detail_json](crate::utility::RecognitionDetail::detail_json).
+    pub detail: Option<Value>,
+}
+
+impl MaaMsgRecognition {
+    fn parse(recognition: &Value) -> Option<Self> {
+        match recognition {
+            Value::Object(map) if !map.is_empty() => {
+                serde_json::from_value(recognition.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// The score of the hit, if the algorithm reports one.
+    ///
+    /// This is the score of the `best` entry of the detail, or of the candidate whose box is the
+    /// hit box when the detail has no such entry.
+    pub fn score(&self) -> Option<f64> {
+        let detail = self.detail.as_ref()?;
+        let best = match detail.get("best") {
+            Some(best) => best,
+            None => {
+                let hit_box = self.hit_box?;
+                detail_candidates(detail).iter().find(|candidate| {
+                    candidate
+                        .get("box")
+                        .and_then(|b| <[i32; 4]>::deserialize(b).ok())
+                        == Some(hit_box)
+                })?
+            }
+        };
+
+        best.get("score")?.as_f64()
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum MaaMsg {
@@ -114,6 +189,12 @@ pub enum MaaMsg {
     TaskDebugCompleted(MaaMsgTaskDebug),
     TaskDebugListToRecognize,
     TaskDebugHit,
+
+    /// A message this crate does not know, e.g. from a newer MaaFramework, with its raw details.
+    Unknown {
+        msg: String,
+        details: Value,
+    },
 }
 
 impl MaaMsg {
@@ -209,9 +290,140 @@ impl MaaMsg {
             }
             "Task.Debug.ListToRecognize" => MaaMsg::TaskDebugListToRecognize,
             "Task.Debug.Hit" => MaaMsg::TaskDebugHit,
-            _ => MaaMsg::Invalid,
+            _ => MaaMsg::Unknown {
+                msg: msg.to_owned(),
+                details: serde_json::from_str(details)
+                    .unwrap_or_else(|_| Value::String(details.to_owned())),
+            },
         };
 
         Ok(value)
     }
 }
+
+/// A message together with the time it was received.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaaTimedMsg {
+    pub msg: MaaMsg,
+    pub at: SystemTime,
+}
+
+/// A [CallbackHandler] that stamps every message with the time it was received before passing it
+/// on, e.g. to measure how long recognitions take.
+pub struct TimedHandler<F> {
+    handler: F,
+}
+
+impl<F> TimedHandler<F>
+where
+    F: FnMut(MaaTimedMsg),
+{
+    pub fn new(handler: F) -> Self {
+        TimedHandler { handler }
+    }
+}
+
+impl<F> CallbackHandler for TimedHandler<F>
+where
+    F: FnMut(MaaTimedMsg),
+{
+    fn handle(&mut self, msg: MaaMsg) {
+        let at = SystemTime::now();
+        (self.handler)(MaaTimedMsg { msg, at });
+    }
+}
+
+/// A [CallbackHandler] that lets other threads block until a given message arrives.
+///
+/// Pass a clone as the handler of an instance, controller or resource, then wait on the original:
+///
+/// ```ignore
+/// let waiter = MaaMsgWaiter::default();
+/// let instance = MaaInstance::new(Some(waiter.clone()));
+/// // ...
+/// let msg = waiter.wait_for(
+///     |msg| matches!(msg, MaaMsg::TaskFocusHit(focus) if focus.name == "StartGame"),
+///     Duration::from_secs(30),
+/// )?;
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct MaaMsgWaiter {
+    waiters: Arc<Mutex<Vec<Sender<MaaMsg>>>>,
+}
+
+impl MaaMsgWaiter {
+    /// Block until a message matching `predicate` arrives and return it.
+    ///
+    /// Only messages received after the call are considered.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::MaaMsgWaitTimeoutError] if no message matched within `timeout`.
+    pub fn wait_for<P>(&self, predicate: P, timeout: Duration) -> MaaResult<MaaMsg>
+    where
+        P: Fn(&MaaMsg) -> bool,
+    {
+        let (sender, receiver) = channel();
+        self.waiters.lock().unwrap().push(sender);
+
+        // the sender is dropped by the handler once the receiver is gone
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match receiver.recv_timeout(left) {
+                Ok(msg) if predicate(&msg) => return Ok(msg),
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                    return Err(Error::MaaMsgWaitTimeoutError)
+                }
+            }
+        }
+    }
+}
+
+impl CallbackHandler for MaaMsgWaiter {
+    fn handle(&mut self, msg: MaaMsg) {
+        self.waiters
+            .lock()
+            .unwrap()
+            .retain(|waiter| waiter.send(msg.clone()).is_ok());
+    }
+}
+
+/// A [CallbackHandler] that forwards every message to the `log` crate.
+///
+/// Failures are logged as warnings, everything the framework reports during normal operation as
+/// info and the task debug messages as debug.
+#[cfg(feature = "log")]
+#[doc(cfg(feature = "log"))]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogHandler;
+
+#[cfg(feature = "log")]
+impl CallbackHandler for LogHandler {
+    fn handle(&mut self, msg: MaaMsg) {
+        let level = match msg {
+            MaaMsg::Invalid => log::Level::Error,
+
+            MaaMsg::ResourceLoadingFailed(_)
+            | MaaMsg::ControllerUUIDGetFailed
+            | MaaMsg::ControllerResolutionGetFailed
+            | MaaMsg::ControllerScreencapInitFailed
+            | MaaMsg::ControllerTouchInputInitFailed
+            | MaaMsg::ControllerKeyInputInitFailed
+            | MaaMsg::ControllerConnectFailed(_)
+            | MaaMsg::ControllerActionFailed(_)
+            | MaaMsg::TaskFailed(_) => log::Level::Warn,
+
+            MaaMsg::TaskDebugReadyToTun(_)
+            | MaaMsg::TaskDebugRunout(_)
+            | MaaMsg::TaskDebugCompleted(_)
+            | MaaMsg::TaskDebugListToRecognize
+            | MaaMsg::TaskDebugHit => log::Level::Debug,
+
+            _ => log::Level::Info,
+        };
+
+        log::log!(target: "maa_framework", level, "{:?}", msg);
+    }
+}
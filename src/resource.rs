@@ -1,8 +1,14 @@
-use std::{fmt::Display, ops::Deref, ptr::null_mut};
+use std::{
+    fmt::Display,
+    ops::Deref,
+    path::{Path, PathBuf},
+    ptr::null_mut,
+    sync::Mutex,
+};
 
 use crate::{
-    buffer::string_buffer::MaaStringBuffer, internal, maa_bool, CallbackHandler, MaaResult,
-    MaaStatus,
+    buffer::string_buffer::MaaStringBuffer, error::Error, internal, maa_bool, maa_id,
+    CallbackHandler, MaaResult, MaaStatus,
 };
 
 pub use internal::MaaResId;
@@ -37,6 +43,9 @@ impl Display for MaaResOption {
 #[derive(Debug)]
 pub struct MaaResourceInstance<T> {
     pub(crate) handle: internal::MaaResourceHandle,
+    /// Loads posted through this instance and the bundle path of each.
+    posted: Mutex<Vec<(MaaResId, PathBuf)>>,
+    destroy_at_drop: bool,
     _phantom: std::marker::PhantomData<T>,
 }
 
@@ -72,20 +81,159 @@ impl<T> MaaResourceInstance<T> {
 
         MaaResourceInstance {
             handle,
+            posted: Mutex::new(Vec::new()),
+            destroy_at_drop: true,
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Wrap a resource owned by someone else, e.g. the one bound to an instance. It is not
+    /// destroyed on drop.
     pub(crate) fn new_from_handle(handle: internal::MaaResourceHandle) -> Self {
         MaaResourceInstance {
             handle,
+            posted: Mutex::new(Vec::new()),
+            destroy_at_drop: false,
             _phantom: std::marker::PhantomData,
         }
     }
 
-    pub fn post_path(&self, path: &str) -> MaaResId {
-        let path = internal::to_cstring(path);
-        unsafe { internal::MaaResourcePostPath(self.handle, path) }
+    /// Queue loading the resource at `path`.
+    ///
+    /// MaaFramework runs the loads posted to a resource one after another, so posting while an
+    /// earlier load is still running is safe: the new load waits for it. Use the returned id with
+    /// [wait](Self::wait), or [load_progress](Self::load_progress) for all of them.
+    pub fn post_path(&self, path: &str) -> MaaResult<MaaResId> {
+        let path_str = internal::to_cstring(path);
+        let id = unsafe { internal::MaaResourcePostPath(self.handle, path_str) };
+
+        let id = maa_id!(id, MaaResourcePostPathError, path.to_owned())?;
+        self.posted.lock().unwrap().push((id, PathBuf::from(path)));
+        Ok(id)
+    }
+
+    /// Load the resource at `path`, wait for it and, if `expected_hash` is given, check that the
+    /// [hash](Self::get_hash) of the resource matches it.
+    ///
+    /// The hash covers everything loaded into this resource, not only `path`.
+    pub fn post_path_verified(&self, path: &str, expected_hash: Option<&str>) -> MaaResult<()> {
+        let id = self.post_path(path)?;
+        if !matches!(self.wait(id)?, MaaStatus::Success) {
+            return Err(Error::MaaResourceLoadError(path.to_owned()));
+        }
+
+        if let Some(expected) = expected_hash {
+            let actual = self.get_hash()?;
+            if actual != expected {
+                return Err(Error::MaaResourceHashMismatchError {
+                    expected: expected.to_owned(),
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fraction of the paths posted through this instance that are done loading, from 0.0 to 1.0.
+    ///
+    /// Failed loads count as done. Returns 1.0 if nothing was posted.
+    pub fn load_progress(&self) -> MaaResult<f32> {
+        let posted = self.posted.lock().unwrap();
+        if posted.is_empty() {
+            return Ok(1.0);
+        }
+
+        let mut done = 0;
+        for &(id, _) in posted.iter() {
+            if !matches!(self.status(id)?, MaaStatus::Pending | MaaStatus::Running) {
+                done += 1;
+            }
+        }
+
+        Ok(done as f32 / posted.len() as f32)
+    }
+
+    /// Where the framework finds the model at `relative`, e.g. `ocr` for the default OCR model
+    /// or `classify/foo.onnx`, relative to the `model` directory of the bundles.
+    ///
+    /// Bundles loaded later override earlier ones, so they are searched first. Returns `None` if
+    /// no bundle posted through this instance has it.
+    pub fn resolve_model_path(&self, relative: &str) -> MaaResult<Option<PathBuf>> {
+        let posted = self.posted.lock().unwrap();
+        let found = posted
+            .iter()
+            .rev()
+            .map(|(_, bundle)| bundle.join("model").join(relative))
+            .find(|path| path.exists());
+
+        Ok(found)
+    }
+
+    /// The files under the `model` directory of every bundle posted through this instance,
+    /// relative to it, e.g. `ocr/det.onnx`. A file overridden by a later bundle is listed once.
+    pub fn list_models(&self) -> MaaResult<Vec<PathBuf>> {
+        fn collect(root: &Path, dir: &Path, models: &mut Vec<PathBuf>) -> MaaResult<()> {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    collect(root, &path, models)?;
+                } else if let Ok(relative) = path.strip_prefix(root) {
+                    models.push(relative.to_path_buf());
+                }
+            }
+            Ok(())
+        }
+
+        let mut models = Vec::new();
+        for (_, bundle) in self.posted.lock().unwrap().iter() {
+            let root = bundle.join("model");
+            if root.is_dir() {
+                collect(&root, &root, &mut models)?;
+            }
+        }
+        models.sort();
+        models.dedup();
+
+        Ok(models)
+    }
+
+    /// The OCR models of the bundles posted through this instance, as the names used by the
+    /// `model` field of OCR nodes: `""` for the default one in `model/ocr`, and the name of each
+    /// subdirectory of it that holds a model.
+    ///
+    /// MaaFramework 1.8 has no option to change the default model, set `model` in the task param
+    /// of the OCR nodes instead.
+    pub fn list_ocr_models(&self) -> MaaResult<Vec<String>> {
+        fn is_model(dir: &Path) -> bool {
+            ["det.onnx", "rec.onnx", "keys.txt"]
+                .iter()
+                .all(|file| dir.join(file).is_file())
+        }
+
+        let mut models = Vec::new();
+        for (_, bundle) in self.posted.lock().unwrap().iter() {
+            let root = bundle.join("model").join("ocr");
+            if !root.is_dir() {
+                continue;
+            }
+
+            if is_model(&root) {
+                models.push(String::new());
+            }
+            for entry in std::fs::read_dir(&root)? {
+                let path = entry?.path();
+                if path.is_dir() && is_model(&path) {
+                    if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                        models.push(name.to_owned());
+                    }
+                }
+            }
+        }
+        models.sort();
+        models.dedup();
+
+        Ok(models)
     }
 
     pub fn status(&self, id: MaaResId) -> MaaResult<MaaStatus> {
@@ -152,6 +300,7 @@ impl<T> MaaResourceInstance<T> {
         let ret = unsafe { internal::MaaResourceClear(self.handle) };
 
         if maa_bool!(ret) {
+            self.posted.lock().unwrap().clear();
             Ok(())
         } else {
             Err(crate::error::Error::MaaResourceClearError)
@@ -161,8 +310,10 @@ impl<T> MaaResourceInstance<T> {
 
 impl<T> Drop for MaaResourceInstance<T> {
     fn drop(&mut self) {
-        unsafe {
-            internal::MaaResourceDestroy(self.handle);
+        if self.destroy_at_drop {
+            unsafe {
+                internal::MaaResourceDestroy(self.handle);
+            }
         }
     }
 }
@@ -0,0 +1,145 @@
+//! Set up a connected controller, a loaded resource and an instance bound to both in one go.
+
+#[cfg(feature = "custom_action")]
+use crate::custom::custom_action::MaaCustomAction;
+#[cfg(feature = "custom_recognizer")]
+use crate::custom::custom_recognizer::MaaCustomRecognizer;
+use crate::{
+    controller::MaaControllerInstance,
+    error::{Error, ResultExt},
+    instance::MaaInstance,
+    resource::MaaResourceInstance,
+    CallbackHandler, MaaResult, MaaStatus,
+};
+
+type Registration<T> = Box<dyn FnOnce(&mut MaaInstance<T>) -> MaaResult<()>>;
+
+/// Builder for a ready to use `(MaaInstance, MaaControllerInstance, MaaResourceInstance)`.
+///
+/// # Example
+///
+/// ```ignore
+/// use maa_framework::session::SessionBuilder;
+///
+/// let controller = MaaControllerInstance::new_adb(adb_path, address, controller_type, "{}", agent_path, None);
+/// let (instance, controller, resource) = SessionBuilder::new(controller)
+///     .resource_path("resource")
+///     .build()?;
+/// ```
+pub struct SessionBuilder<T> {
+    controller: MaaControllerInstance<T>,
+    resource_paths: Vec<String>,
+    resource_handler: Option<T>,
+    instance_handler: Option<T>,
+    registrations: Vec<Registration<T>>,
+}
+
+impl<T> SessionBuilder<T> {
+    /// Start a session from a controller that has been created but not connected yet.
+    pub fn new(controller: MaaControllerInstance<T>) -> Self {
+        SessionBuilder {
+            controller,
+            resource_paths: Vec::new(),
+            resource_handler: None,
+            instance_handler: None,
+            registrations: Vec::new(),
+        }
+    }
+
+    /// Add a resource path to load. Paths are loaded in the order they are added.
+    pub fn resource_path(mut self, path: &str) -> Self {
+        self.resource_paths.push(path.to_owned());
+        self
+    }
+
+    /// Callback handler for the created resource.
+    pub fn resource_handler(mut self, handler: T) -> Self {
+        self.resource_handler = Some(handler);
+        self
+    }
+
+    /// Callback handler for the created instance.
+    pub fn instance_handler(mut self, handler: T) -> Self {
+        self.instance_handler = Some(handler);
+        self
+    }
+
+    #[cfg(feature = "custom_recognizer")]
+    #[doc(cfg(feature = "custom_recognizer"))]
+    pub fn custom_recognizer<R>(mut self, name: &str, recognizer: R) -> Self
+    where
+        R: MaaCustomRecognizer + Send + Sync + 'static,
+    {
+        let name = name.to_owned();
+        self.registrations.push(Box::new(move |instance| {
+            instance.register_custom_recognizer(&name, recognizer)
+        }));
+        self
+    }
+
+    #[cfg(feature = "custom_action")]
+    #[doc(cfg(feature = "custom_action"))]
+    pub fn custom_action<A>(mut self, name: &str, action: A) -> Self
+    where
+        A: MaaCustomAction + Send + Sync + 'static,
+    {
+        let name = name.to_owned();
+        self.registrations.push(Box::new(move |instance| {
+            instance.register_custom_action(&name, action)
+        }));
+        self
+    }
+
+    /// Connect the controller, load the resource, create the instance, bind both and register
+    /// the custom components.
+    ///
+    /// The error names the step that failed.
+    pub fn build(
+        self,
+    ) -> MaaResult<(
+        MaaInstance<T>,
+        MaaControllerInstance<T>,
+        MaaResourceInstance<T>,
+    )>
+    where
+        T: CallbackHandler,
+    {
+        let controller = self.controller;
+        let id = controller
+            .post_connect()
+            .with_context("connecting controller")?;
+        if !matches!(controller.wait(id)?, MaaStatus::Success) || !controller.connected() {
+            let error = Err(Error::MaaControllerConnectError);
+            return match controller.last_error() {
+                Some(why) => error.with_context(&why),
+                None => error,
+            };
+        }
+
+        let resource = MaaResourceInstance::new(self.resource_handler);
+        for path in &self.resource_paths {
+            let id = resource.post_path(path).with_context("loading resource")?;
+            if !matches!(resource.wait(id)?, MaaStatus::Success) {
+                return Err(Error::MaaResourceLoadError(path.clone()));
+            }
+        }
+
+        let mut instance = MaaInstance::new(self.instance_handler);
+        instance
+            .bind_resource(&resource)
+            .with_context("binding resource")?;
+        instance
+            .bind_controller(&controller)
+            .with_context("binding controller")?;
+
+        for register in self.registrations {
+            register(&mut instance).with_context("registering custom component")?;
+        }
+
+        if !instance.inited() {
+            return Err(Error::MaaInstanceInitError);
+        }
+
+        Ok((instance, controller, resource))
+    }
+}
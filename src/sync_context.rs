@@ -3,12 +3,13 @@ use crate::{
         image_buffer::MaaImageBuffer, rect_buffer::MaaRectBuffer, string_buffer::MaaStringBuffer,
     },
     error::Error,
-    instance::TaskParam,
-    internal, maa_bool, MaaResult,
+    instance::{MaaTaskId, TaskParam},
+    internal, maa_bool, maa_id, MaaResult,
 };
 
 pub struct MaaSyncContext {
     handle: internal::MaaSyncContextHandle,
+    instance: internal::MaaInstanceHandle,
 }
 
 unsafe impl Send for MaaSyncContext {}
@@ -16,11 +17,46 @@ unsafe impl Sync for MaaSyncContext {}
 
 impl From<internal::MaaSyncContextHandle> for MaaSyncContext {
     fn from(handle: internal::MaaSyncContextHandle) -> Self {
-        MaaSyncContext { handle }
+        MaaSyncContext {
+            handle,
+            instance: std::ptr::null_mut(),
+        }
     }
 }
 
 impl MaaSyncContext {
+    #[cfg(any(feature = "custom_recognizer", feature = "custom_action"))]
+    pub(crate) fn with_instance(
+        handle: internal::MaaSyncContextHandle,
+        instance: internal::MaaInstanceHandle,
+    ) -> Self {
+        MaaSyncContext { handle, instance }
+    }
+
+    /// Post a task to the instance running the current one.
+    ///
+    /// The task is only queued and starts after the current task finishes, so this is safe to
+    /// call from within a custom recognizer or action. Do not wait for the returned id from
+    /// there, since that would block the task queue on itself.
+    ///
+    /// The task is not tracked by [MaaInstance::pending_tasks](crate::instance::MaaInstance::pending_tasks).
+    pub fn post_task<T>(&self, entry: &str, param: T) -> MaaResult<MaaTaskId>
+    where
+        T: TaskParam,
+    {
+        if self.instance.is_null() {
+            return Err(Error::MaaSyncContextPostTaskError(entry.to_owned()));
+        }
+
+        let param = param.get_param();
+        let entry_str = internal::to_cstring(entry);
+        let param = internal::to_cstring(&param);
+
+        let id = unsafe { internal::MaaPostTask(self.instance, entry_str, param) };
+
+        maa_id!(id, MaaSyncContextPostTaskError, entry.to_owned())
+    }
+
     pub fn run_task<T>(&self, task_name: &str, param: T) -> MaaResult<()>
     where
         T: TaskParam,
@@ -174,3 +210,18 @@ impl MaaSyncContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn post_task_without_instance_fails() {
+        let context = MaaSyncContext::from(std::ptr::null_mut());
+
+        assert!(matches!(
+            context.post_task("Entry", serde_json::json!({})),
+            Err(Error::MaaSyncContextPostTaskError(_))
+        ));
+    }
+}
@@ -1,12 +1,18 @@
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 
-use crate::{error::Error, instance::MaaInstance, internal, maa_bool, string, MaaResult};
+use crate::{error::Error, instance::MaaInstance, internal, maa_bool, MaaResult};
 
 #[cfg(feature = "win32")]
 use crate::controller::win32::MaaWin32Hwnd;
 
 #[cfg(feature = "adb")]
-use crate::controller::adb::MaaAdbControllerType;
+use crate::{
+    controller::{adb::MaaAdbControllerType, MaaControllerInstance},
+    error::ResultExt,
+    string, CallbackHandler, MaaStatus,
+};
+#[cfg(feature = "adb")]
+use serde::Deserialize;
 
 pub struct MaaToolkit;
 
@@ -79,6 +85,39 @@ impl MaaToolkit {
         self.get_adb_devices_info(device_count)
     }
 
+    /// Find the adb devices, create a controller for the first one and connect it.
+    ///
+    /// Fails with [Error::MaaToolkitNoDeviceError] if no device is found, and with
+    /// [Error::MaaControllerConnectError], in the context of
+    /// [last_error](MaaControllerInstance::last_error), if the connection fails.
+    #[cfg(feature = "adb")]
+    #[doc(cfg(feature = "adb"))]
+    pub fn connect_first_adb<T>(
+        &self,
+        agent_path: &str,
+        handler: Option<T>,
+    ) -> MaaResult<MaaControllerInstance<T>>
+    where
+        T: CallbackHandler,
+    {
+        let devices = self.find_adb_device()?;
+        let device = devices.first().ok_or(Error::MaaToolkitNoDeviceError)?;
+
+        let controller = MaaControllerInstance::from_adb_device(device, agent_path, handler);
+        let id = controller
+            .post_connect()
+            .with_context(&format!("connecting to {}", device.adb_serial))?;
+        if !matches!(controller.wait(id)?, MaaStatus::Success) || !controller.connected() {
+            let error = Err(Error::MaaControllerConnectError);
+            return match controller.last_error() {
+                Some(why) => error.with_context(&why),
+                None => error,
+            };
+        }
+
+        Ok(controller)
+    }
+
     #[cfg(feature = "adb")]
     #[doc(cfg(feature = "adb"))]
     fn get_adb_devices_info(&self, device_count: u64) -> MaaResult<Vec<AdbDeviceInfo>> {
@@ -111,12 +150,12 @@ impl MaaToolkit {
 
     pub fn register_custom_recognizer_executor<T>(
         &self,
-        handle: MaaInstance<T>,
+        handle: &mut MaaInstance<T>,
         recognizer_name: &str,
         recognizer_exec_path: &str,
         recognizer_exec_params: Vec<String>,
     ) -> MaaResult<()> {
-        let recognizer_name = internal::to_cstring(recognizer_name);
+        let name = internal::to_cstring(recognizer_name);
         let recognizer_exec_path = internal::to_cstring(recognizer_exec_path);
 
         let param_size = recognizer_exec_params.len() as u64;
@@ -130,8 +169,8 @@ impl MaaToolkit {
 
         let ret = unsafe {
             internal::MaaToolkitRegisterCustomRecognizerExecutor(
-                *handle,
-                recognizer_name,
+                handle.handle,
+                name,
                 recognizer_exec_path,
                 params_ptr,
                 param_size,
@@ -142,35 +181,42 @@ impl MaaToolkit {
             return Err(Error::MaaToolkitRegisterCustomRecognizerExecutorError);
         }
 
+        handle
+            .registered_recognizer_executors
+            .insert(recognizer_name.to_owned());
+
         Ok(())
     }
 
     pub fn unregister_custom_recognizer_executor<T>(
         &self,
-        handle: MaaInstance<T>,
+        handle: &mut MaaInstance<T>,
         recognizer_name: &str,
     ) -> MaaResult<()> {
-        let recognizer_name = internal::to_cstring(recognizer_name);
+        let name = internal::to_cstring(recognizer_name);
 
-        let ret = unsafe {
-            internal::MaaToolkitUnregisterCustomRecognizerExecutor(*handle, recognizer_name)
-        };
+        let ret =
+            unsafe { internal::MaaToolkitUnregisterCustomRecognizerExecutor(handle.handle, name) };
 
         if !maa_bool!(ret) {
             return Err(Error::MaaToolkitUnregisterCustomRecognizerExecutorError);
         }
 
+        handle
+            .registered_recognizer_executors
+            .remove(recognizer_name);
+
         Ok(())
     }
 
     pub fn register_custom_action_executor<T>(
         &self,
-        handle: MaaInstance<T>,
+        handle: &mut MaaInstance<T>,
         action_name: &str,
         action_exec_path: &str,
         action_exec_params: Vec<String>,
     ) -> MaaResult<()> {
-        let action_name = internal::to_cstring(action_name);
+        let name = internal::to_cstring(action_name);
         let action_exec_path = internal::to_cstring(action_exec_path);
 
         let param_size = action_exec_params.len() as u64;
@@ -184,8 +230,8 @@ impl MaaToolkit {
 
         let ret = unsafe {
             internal::MaaToolkitRegisterCustomActionExecutor(
-                *handle,
-                action_name,
+                handle.handle,
+                name,
                 action_exec_path,
                 params_ptr,
                 param_size,
@@ -196,23 +242,29 @@ impl MaaToolkit {
             return Err(Error::MaaToolkitRegisterCustomRecognizerExecutorError);
         }
 
+        handle
+            .registered_action_executors
+            .insert(action_name.to_owned());
+
         Ok(())
     }
 
     pub fn unregister_custom_action_executor<T>(
         &self,
-        handle: MaaInstance<T>,
+        handle: &mut MaaInstance<T>,
         action_name: &str,
     ) -> MaaResult<()> {
-        let action_name = internal::to_cstring(action_name);
+        let name = internal::to_cstring(action_name);
 
         let ret =
-            unsafe { internal::MaaToolkitUnregisterCustomActionExecutor(*handle, action_name) };
+            unsafe { internal::MaaToolkitUnregisterCustomActionExecutor(handle.handle, name) };
 
         if !maa_bool!(ret) {
             return Err(Error::MaaToolkitUnregisterCustomRecognizerExecutorError);
         }
 
+        handle.registered_action_executors.remove(action_name);
+
         Ok(())
     }
 
@@ -290,7 +342,7 @@ impl MaaToolkit {
 unsafe impl Send for MaaToolkit {}
 unsafe impl Sync for MaaToolkit {}
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg(feature = "adb")]
 pub struct AdbDeviceInfo {
     pub name: String,
@@ -7,6 +7,7 @@ use crate::{
         image_list_buffer::MaaImageListBuffer, rect_buffer::MaaRectBuffer,
         string_buffer::MaaStringBuffer,
     },
+    common::Rect,
     Error,
     internal, maa_bool, MaaResult,
 };
@@ -119,22 +120,26 @@ pub struct NodeDetail {
     pub successful: bool,
 }
 
-pub fn query_node_detail(node_id: i64) -> NodeDetail {
+pub fn query_node_detail(node_id: i64) -> MaaResult<NodeDetail> {
     let mut reco_id: i64 = 0;
     let mut successful: u8 = 0;
 
     let name = MaaStringBuffer::new();
 
-    unsafe {
-        internal::MaaQueryNodeDetail(node_id, name.handle, &mut reco_id, &mut successful);
+    let ret = unsafe {
+        internal::MaaQueryNodeDetail(node_id, name.handle, &mut reco_id, &mut successful)
+    };
+
+    if !maa_bool!(ret) {
+        return Err(Error::MaaQueryNodeDetailError(node_id));
     }
 
     let successful = maa_bool!(successful);
-    NodeDetail {
+    Ok(NodeDetail {
         name: name.string(),
         reco_id,
         successful,
-    }
+    })
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -143,36 +148,236 @@ pub struct TaskDetail {
     pub node_id_list: Vec<i64>,
 }
 
-pub fn query_task_detail(task_id: i64) -> TaskDetail {
+/// Why a task ended without completing, as classified by [TaskDetail::failure_reason].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FailureReason {
+    /// No node ran, the entry was never recognized.
+    NothingRun { entry: String },
+    /// The last node ran without its recognition hitting.
+    RecognitionMissed { node: String },
+    /// The last node was recognized but did not complete its action.
+    ActionFailed { node: String },
+}
+
+impl TaskDetail {
+    /// Classify why the task failed by looking at its last node.
+    ///
+    /// Returns `None` if the last node completed. A task stopped with
+    /// [post_stop](crate::instance::MaaInstance::post_stop) or by running out of time reports the
+    /// node it was on, since the framework does not record the cause.
+    pub fn failure_reason(&self) -> MaaResult<Option<FailureReason>> {
+        let Some(&node_id) = self.node_id_list.last() else {
+            return Ok(Some(FailureReason::NothingRun {
+                entry: self.entry.clone(),
+            }));
+        };
+
+        let node = query_node_detail(node_id)?;
+        if node.successful {
+            return Ok(None);
+        }
+
+        let recognition = query_recognition_detail_with(node.reco_id, false)?;
+        let reason = if recognition.hit {
+            FailureReason::ActionFailed { node: node.name }
+        } else {
+            FailureReason::RecognitionMissed { node: node.name }
+        };
+
+        Ok(Some(reason))
+    }
+}
+
+/// Everything recorded about a task run, for archiving or attaching to bug reports.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunReport {
+    pub task_id: i64,
+    pub entry: String,
+    pub nodes: Vec<NodeReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeReport {
+    pub node_id: i64,
+    pub name: String,
+    pub successful: bool,
+    pub recognition: RecognitionReport,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecognitionReport {
+    pub reco_id: i64,
+    pub name: String,
+    pub hit: bool,
+    /// `[x, y, width, height]`, if hit.
+    pub hit_box: Option<[i32; 4]>,
+    pub detail: serde_json::Value,
+}
+
+/// Collect the details of a task and of all its nodes and recognitions into a [RunReport].
+///
+/// Images are not included.
+pub fn query_run_report(task_id: i64) -> MaaResult<RunReport> {
+    let task = query_task_detail(task_id)?;
+
+    let mut nodes = Vec::with_capacity(task.node_id_list.len());
+    for node_id in task.node_id_list {
+        let node = query_node_detail(node_id)?;
+        let reco = query_recognition_detail_with(node.reco_id, false)?;
+
+        nodes.push(NodeReport {
+            node_id,
+            name: node.name,
+            successful: node.successful,
+            recognition: RecognitionReport {
+                reco_id: reco.reco_id,
+                hit_box: reco.best_box().map(Into::into),
+                detail: serde_json::from_str(&reco.detail_json).unwrap_or_default(),
+                name: reco.name,
+                hit: reco.hit,
+            },
+        });
+    }
+
+    Ok(RunReport {
+        task_id,
+        entry: task.entry,
+        nodes,
+    })
+}
+
+pub fn query_task_detail(task_id: i64) -> MaaResult<TaskDetail> {
     // first get size
     let mut size: u64 = 0;
-    unsafe {
-        internal::MaaQueryTaskDetail(task_id, null_mut(), null_mut(), &mut size);
+    let ret = unsafe { internal::MaaQueryTaskDetail(task_id, null_mut(), null_mut(), &mut size) };
+
+    if !maa_bool!(ret) {
+        return Err(Error::MaaQueryTaskDetailError(task_id));
     }
 
     let entry = MaaStringBuffer::new();
     let mut node_id_list = vec![0; size as usize];
 
-    unsafe {
-        internal::MaaQueryTaskDetail(task_id, entry.handle, node_id_list.as_mut_ptr(), &mut size);
+    let ret = unsafe {
+        internal::MaaQueryTaskDetail(task_id, entry.handle, node_id_list.as_mut_ptr(), &mut size)
+    };
+
+    if !maa_bool!(ret) {
+        return Err(Error::MaaQueryTaskDetailError(task_id));
     }
 
-    TaskDetail {
+    Ok(TaskDetail {
         entry: entry.string(),
         node_id_list,
-    }
+    })
 }
 
 pub struct RecognitionDetail {
+    pub reco_id: i64,
     pub name: String,
     pub hit: bool,
     pub hit_box: MaaRectBuffer,
     pub detail_json: String,
+    /// Empty if the detail was queried without images.
     pub raw: MaaImageBuffer,
+    /// Empty if the detail was queried without images.
     pub draws: MaaImageListBuffer,
 }
 
-pub fn query_recognition_detail(reco_id: i64) -> RecognitionDetail {
+impl RecognitionDetail {
+    /// The box the recognition hit.
+    pub fn best_box(&self) -> Option<Rect> {
+        self.hit.then(|| Rect::from(&self.hit_box))
+    }
+
+    /// Every candidate box of the recognition.
+    ///
+    /// These are read from the `all` list of the detail, or from the detail itself when it is a
+    /// list of results. Algorithms without candidates, like `DirectHit`, give an empty list.
+    pub fn all_boxes(&self) -> Vec<Rect> {
+        let Ok(detail) = serde_json::from_str(&self.detail_json) else {
+            return Vec::new();
+        };
+
+        detail_candidates(&detail)
+            .iter()
+            .filter_map(|candidate| <[i32; 4]>::deserialize(candidate.get("box")?).ok())
+            .map(Rect::from)
+            .collect()
+    }
+
+    /// Fetch the raw image of the recognition.
+    ///
+    /// Useful when the detail was queried without images, see [query_recognition_detail_with].
+    pub fn raw_image_lazy(&self) -> MaaResult<MaaImageBuffer> {
+        let raw = MaaImageBuffer::new();
+
+        let ret = unsafe {
+            internal::MaaQueryRecognitionDetail(
+                self.reco_id,
+                null_mut(),
+                null_mut(),
+                null_mut(),
+                null_mut(),
+                raw.handle,
+                null_mut(),
+            )
+        };
+
+        if !maa_bool!(ret) {
+            return Err(Error::MaaQueryRecognitionDetailError(self.reco_id));
+        }
+
+        Ok(raw)
+    }
+}
+
+/// The candidate list in the detail JSON of a recognition.
+///
+/// This is the `all` list when the detail has one, otherwise the detail itself when it is a list
+/// of results, as for template, feature, color, OCR and neural network matches. Nested details,
+/// e.g. of the sub recognitions of a custom recognizer, are not included.
+pub(crate) fn detail_candidates(detail: &serde_json::Value) -> &[serde_json::Value] {
+    match detail.get("all").unwrap_or(detail) {
+        serde_json::Value::Array(candidates) => candidates,
+        _ => &[],
+    }
+}
+
+/// Fetch only the draw images of a recognition, e.g. to render a live overlay.
+///
+/// Draws are only produced while drawing is enabled, e.g. with [MaaGlobalOption::SaveDraw].
+pub fn query_recognition_draws(reco_id: i64) -> MaaResult<MaaImageListBuffer> {
+    let draws = MaaImageListBuffer::new();
+
+    let ret = unsafe {
+        internal::MaaQueryRecognitionDetail(
+            reco_id,
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            draws.handle,
+        )
+    };
+
+    if !maa_bool!(ret) {
+        return Err(Error::MaaQueryRecognitionDetailError(reco_id));
+    }
+
+    Ok(draws)
+}
+
+pub fn query_recognition_detail(reco_id: i64) -> MaaResult<RecognitionDetail> {
+    query_recognition_detail_with(reco_id, true)
+}
+
+/// Query a recognition detail, copying the raw and draw images only if `with_images` is set.
+pub fn query_recognition_detail_with(
+    reco_id: i64,
+    with_images: bool,
+) -> MaaResult<RecognitionDetail> {
     let name = MaaStringBuffer::new();
     let mut hit: u8 = 0;
     let hit_box: MaaRectBuffer = Default::default();
@@ -180,26 +385,37 @@ pub fn query_recognition_detail(reco_id: i64) -> RecognitionDetail {
     let raw = MaaImageBuffer::new();
     let draws: MaaImageListBuffer = Default::default();
 
-    unsafe {
+    let (raw_handle, draws_handle) = if with_images {
+        (raw.handle, draws.handle)
+    } else {
+        (null_mut(), null_mut())
+    };
+
+    let ret = unsafe {
         internal::MaaQueryRecognitionDetail(
             reco_id,
             name.handle,
             &mut hit,
             hit_box.handle,
             detail_json.handle,
-            raw.handle,
-            draws.handle,
-        );
+            raw_handle,
+            draws_handle,
+        )
+    };
+
+    if !maa_bool!(ret) {
+        return Err(Error::MaaQueryRecognitionDetailError(reco_id));
     }
 
     let hit = maa_bool!(hit);
 
-    RecognitionDetail {
+    Ok(RecognitionDetail {
+        reco_id,
         name: name.string(),
         hit,
         hit_box,
         detail_json: detail_json.string(),
         raw,
         draws,
-    }
+    })
 }